@@ -1,9 +1,10 @@
 use api::{achievement_fetch::{GameAchievement}, game_fetch};
-use db::{steam_id_store, achievement_store, excluded_achievement_store, request_store, game_completion_cache};
-use goals_lib::{goals};
+use db::{collection_store, note_store, progress_snapshot_store, schema_change_store, skipped_achievement_store, steam_id_store, achievement_store, excluded_achievement_store, excluded_achievement_store::ExclusionReason, exclusion_rule_store, exclusion_rule_store::RuleKind, request_store, request_store::QuotaTimezone, game_completion_cache, game_completion_cache::CompletionFormula};
+use goals_lib::{autopilot::Autopilot, bingo, daily, exclusion_rules, goal_set::RarityRule, goals, multi_roll, roll, roll::Roller, score, completion, completion::{CompletionPolicy, GameProgress}, backup, backup::ImportMode, doctor, estimate, search, settings, time_estimate};
 
-use std::{collections::HashMap, env, io};
+use std::{collections::{HashMap, HashSet}, env, io, io::Write, sync::{Arc, atomic::{AtomicBool, Ordering}}};
 use clap::Parser;
+use chrono::{Local, NaiveDate};
 
 // Command line arguments
 #[derive(Parser, Debug)]
@@ -21,6 +22,14 @@ struct Args {
     #[arg(long)]
     random_game: bool,
 
+    /// Only pick games estimated to take at most this many hours to finish with --random-game, uses the cached completion
+    #[arg(long)]
+    under_hours: Option<f64>,
+
+    /// Import how many hours games take to beat from a csv file with a line of app_id,hours per game, used to estimate the time left
+    #[arg(long)]
+    import_hours_to_beat: Option<String>,
+
     /// Return a list of the current goals
     #[arg(long)]
     goals: bool,
@@ -29,6 +38,98 @@ struct Args {
     #[arg(long)]
     exclude_achievement: Option<i32>,
 
+    /// Set the priority of a goal by its id, use with --priority
+    #[arg(long)]
+    set_priority: Option<i32>,
+
+    /// The priority for --set-priority, higher goals are listed first
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    priority: i32,
+
+    /// Set the due date of a goal by its id, use with --due or leave it out to clear the date
+    #[arg(long)]
+    set_due: Option<i32>,
+
+    /// The due date for --set-due, in the format YYYY-MM-DD
+    #[arg(long)]
+    due: Option<NaiveDate>,
+
+    /// Only list goals with --goals that are past their due date
+    #[arg(long)]
+    overdue: bool,
+
+    /// Only list goals with --goals that are due within this many days
+    #[arg(long)]
+    due_soon: Option<i64>,
+
+    /// Remove a goal by its id without excluding it, it can be rolled again after the skip cooldown
+    #[arg(long)]
+    skip_achievement: Option<i32>,
+
+    /// Set the number of days a skipped achievement is left out of random rolls
+    #[arg(long)]
+    skip_cooldown: Option<i64>,
+
+    /// Set the most goals that can be active at once, 0 removes the limit
+    #[arg(long)]
+    goal_limit: Option<i32>,
+
+    /// Set the most goals that can be active at once for a single game, 0 removes the limit
+    #[arg(long)]
+    game_goal_limit: Option<i32>,
+
+    /// Replace completed goals when the goals sync, one of off, same_game or targets
+    #[arg(long)]
+    autopilot: Option<Autopilot>,
+
+    /// Set the number of days a game picked or played recently is less likely to be rolled, 0 gives every game the same chance
+    #[arg(long)]
+    pick_cooldown: Option<i64>,
+
+    /// Return the history of skipped goals
+    #[arg(long)]
+    skips: bool,
+
+    /// Reason for an exclusion, one of multiplayer, dlc, bugged or missable
+    #[arg(long)]
+    exclusion_reason: Option<ExclusionReason>,
+
+    /// Return a list of all excluded achievements
+    #[arg(long)]
+    exclusions: bool,
+
+    /// Update the reason of an exclusion by its id in the exclusions list, clears it if no reason is given
+    #[arg(long)]
+    update_exclusion: Option<i32>,
+
+    /// Remove an exclusion by its id in the exclusions list
+    #[arg(long)]
+    remove_exclusion: Option<i32>,
+
+    /// Remove all exclusions for a game
+    #[arg(long)]
+    remove_game_exclusions: bool,
+
+    /// Preview the achievements an exclusion rule would exclude, one of pattern, rarity, hidden or app. Use --save-rule to save it
+    #[arg(long)]
+    exclusion_rule: Option<RuleKind>,
+
+    /// The value for --exclusion-rule, a case insensitive regex matched against the name and description, a percent of players, or an app id
+    #[arg(long)]
+    rule_value: Option<String>,
+
+    /// Save the rule given by --exclusion-rule instead of only previewing it, with the reason given by --exclusion-reason
+    #[arg(long)]
+    save_rule: bool,
+
+    /// Return a list of the exclusion rules
+    #[arg(long)]
+    exclusion_rules: bool,
+
+    /// Delete an exclusion rule by its id in the exclusion rules list
+    #[arg(long)]
+    delete_exclusion_rule: Option<i32>,
+
     /// Return a list of completed games
     #[arg(long)]
     completed_games: bool,
@@ -37,14 +138,180 @@ struct Args {
     #[arg(long)]
     game_completion_list: bool,
 
+    /// Only use games with this tag, also the tag used by --tag-game, --untag-game and --delete-tag
+    #[arg(long)]
+    tag: Option<String>,
+
+    /// Return a list of all tags and their games
+    #[arg(long)]
+    tags: bool,
+
+    /// Add a game to the tag given by --tag, creating the tag if needed
+    #[arg(long)]
+    tag_game: bool,
+
+    /// Remove a game from the tag given by --tag
+    #[arg(long)]
+    untag_game: bool,
+
+    /// Delete the tag given by --tag
+    #[arg(long)]
+    delete_tag: bool,
+
+    /// Set a note on a game, or on a goal with --note-goal, an empty note removes it
+    #[arg(long)]
+    note: Option<String>,
+
+    /// The id of the goal to set the --note on
+    #[arg(long)]
+    note_goal: Option<i32>,
+
+    /// Return a list of all notes
+    #[arg(long)]
+    notes: bool,
+
+    /// Search the text of all notes
+    #[arg(long)]
+    search_notes: Option<String>,
+
+    /// Return the daily progress snapshots, a snapshot is saved each time the completion cache syncs
+    #[arg(long)]
+    progress_history: bool,
+
+    /// Return today's challenge achievement and the streak of challenges completed
+    #[arg(long)]
+    daily: bool,
+
+    /// Set the seed mixed with the date to pick the daily challenge, share it to get the same challenges as others
+    #[arg(long)]
+    daily_seed: Option<u64>,
+
+    /// Generate a bingo card of this width, each square is an achievement from a different game and is added to the goals
+    #[arg(long)]
+    bingo: Option<usize>,
+
+    /// Spread the squares of a new bingo card evenly over common to ultra rare achievements
+    #[arg(long)]
+    balance_rarity: bool,
+
+    /// Return a bingo card by its id, the latest card when no id is given. Squares are marked as their goals are completed
+    #[arg(long, num_args = 0..=1)]
+    bingo_card: Option<Option<i32>>,
+
+    /// Roll a goal from each of this many different games at once, saved together as a session
+    #[arg(long)]
+    multi_roll: Option<usize>,
+
+    /// Refuse a --multi-roll that would take the number of goals over this
+    #[arg(long)]
+    max_goals: Option<usize>,
+
+    /// Only roll from games played at least this many hours with --multi-roll
+    #[arg(long)]
+    min_playtime: Option<i32>,
+
+    /// Only roll from games played at most this many hours with --multi-roll
+    #[arg(long)]
+    max_playtime: Option<i32>,
+
+    /// Only roll achievements at least this percent of players have with --multi-roll
+    #[arg(long)]
+    min_rarity: Option<f64>,
+
+    /// Only roll achievements at most this percent of players have with --multi-roll
+    #[arg(long)]
+    max_rarity: Option<f64>,
+
+    /// Return the games that gained new achievements after they were completed, checked each time the completion cache syncs
+    #[arg(long)]
+    schema_changes: bool,
+
+    /// Dismiss a schema change by its id in the schema changes list
+    #[arg(long)]
+    dismiss_schema_change: Option<i32>,
+
+    /// Search the names and descriptions of achievements in cached schemas and goals
+    #[arg(long)]
+    search: Option<String>,
+
+    /// Only return achievements that are not achieved yet with --search
+    #[arg(long)]
+    unachieved: bool,
+
+    /// Fetch the achievement schema of every played game not yet cached so it can be searched, uses a request per game
+    #[arg(long)]
+    build_search_index: bool,
+
+    /// Return today's Steam API usage broken down by endpoint
+    #[arg(long)]
+    quota: bool,
+
+    /// Set the number of Steam API requests allowed each day
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1..))]
+    request_quota: Option<i32>,
+
+    /// Set the timezone the request quota resets in, one of local, utc or an offset like +10:00
+    #[arg(long)]
+    request_timezone: Option<QuotaTimezone>,
+
+    /// How completion percentages are calculated, one of raw or adjusted (ignores excluded achievements).
+    /// Overrides the completion policy for this command, or changes it with --completion-policy
+    #[arg(long)]
+    completion_formula: Option<CompletionFormula>,
+
+    /// Return what counts as complete and perfect, changing it with --completion-formula, --completion-threshold or --targets-perfect
+    #[arg(long)]
+    completion_policy: bool,
+
+    /// The progress percent a game needs to count as complete, use with --completion-policy
+    #[arg(long, value_parser = clap::value_parser!(i8).range(1..=100))]
+    completion_threshold: Option<i8>,
+
+    /// Whether targets marked as complete also count as perfect, one of true or false, use with --completion-policy
+    #[arg(long)]
+    targets_perfect: Option<bool>,
+
+    /// Return the achievement score of each game, the total and the points gained this month.
+    /// Rarer achievements are worth more, refreshes completion and fetches the rarity of games without it
+    #[arg(long)]
+    scores: bool,
+
     /// Game name used to filter goals
     #[arg(long)]
     game_name: Option<String>,
 
+    /// Export goals, exclusions, targets, tags, notes, skips, settings, progress snapshots, daily challenges, goal sets, exclusion rules, hours to beat and the steam id to a JSON file
+    #[arg(long)]
+    export: Option<String>,
+
+    /// Import goals, exclusions, targets, tags, notes, skips, settings, progress snapshots, daily challenges, goal sets, exclusion rules, hours to beat and the steam id from a JSON file
+    #[arg(long)]
+    import: Option<String>,
+
+    /// How an import treats existing data, one of merge or replace
+    #[arg(long, default_value = "merge")]
+    import_mode: ImportMode,
+
+    /// Check the database for problems
+    #[arg(long)]
+    doctor: bool,
+
+    /// Offer to fix each problem found by --doctor
+    #[arg(long)]
+    fix: bool,
+
     /// Purge specific data tables
     #[arg(long)]
     purge: Option<String>,
 
+    /// Print how many Steam API requests a command would use instead of running it
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Seed the random rolls, the same seed picks the same goal from the same games. Recent picks are not made less likely when a seed is given
+    #[arg(long)]
+    seed: Option<u64>,
+
     /// Show debug level information
     #[arg(short, long)]
     debug: bool,
@@ -61,9 +328,17 @@ async fn main() -> Result<(), reqwest::Error> {
 
     if args.random_achievement {
         let credentials = get_credentials(&args);
-        let game = request_game_name(&credentials.key, &credentials.steam_id).await.expect("No game found for search");
+        let game = request_game_name(&credentials.key, &credentials.steam_id, &args.tag).await.expect("No game found for search");
+        if let Err(e) = goals::check_goal_limit(1).and_then(|_| goals::check_game_goal_limit(&game.appid)) {
+            println!("{e}");
+            return Ok(());
+        }
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::random_achievement())) {
+            return Ok(());
+        }
 
-        let random_achievement: Option<GameAchievement> = goals::get_random_achievement_for_game(&credentials.key, &credentials.steam_id, &game).await;
+        let mut roller = Roller::new(args.seed);
+        let random_achievement: Option<GameAchievement> = goals::get_random_achievement_for_game(&credentials.key, &credentials.steam_id, &game, roller.rng()).await;
         if let Some(a) = random_achievement {
             println!("And your selected achievement is:");
             println!(
@@ -75,8 +350,8 @@ async fn main() -> Result<(), reqwest::Error> {
                     .unwrap_or("no description".to_string())
                 );
             // Save the achievement
-            achievement_store::save_achievement(&a.name, &a.display_name, &a.description, &game.appid, &game.last_played).expect("Failed to save achievement");
-            println!("Saved the achievement!");
+            achievement_store::save_achievement(&a.name, &a.display_name, &a.description, &game.appid, &game.last_played, &Some(roller.stored_seed())).expect("Failed to save achievement");
+            println!("Saved the achievement! Roll again with --seed {seed} to get the same pick", seed = roller.seed());
         }
         else {
             println!("No achievements left in this game!");
@@ -85,12 +360,24 @@ async fn main() -> Result<(), reqwest::Error> {
     else if args.random_game {
         // Fetch games
         let credentials = get_credentials(&args);
-        let mut owned_games: Vec<game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
+        if let Err(e) = goals::check_goal_limit(1) {
+            println!("{e}");
+            return Ok(());
+        }
+        let mut owned_games: Vec<game_fetch::Game> = goals::filter_to_collection(game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await, &args.tag);
+        owned_games.retain(|g| goals::check_game_goal_limit(&g.appid).is_ok());
+        if let Some(hours) = args.under_hours {
+            owned_games = time_estimate::games_under(owned_games, hours, completion_policy(&args));
+            if owned_games.is_empty() {
+                println!("No games are estimated to take under {hours} hours, refresh completion or import hours to beat for more estimates");
+                return Ok(());
+            }
+        }
         let mut game_and_achievement: Option<(game_fetch::Game, GameAchievement)> = None;
-        while !owned_games.is_empty() {
-            let index = (rand::random::<f32>() * owned_games.len() as f32).floor() as usize;
-            let random_game = owned_games.remove(index);
-            let random_achievement: Option<GameAchievement> = goals::get_random_achievement_for_game(&credentials.key, &credentials.steam_id, &random_game).await;
+        let mut roller = Roller::new(args.seed);
+        let mut cooldown = roll::Cooldown::for_roller(&roller);
+        while let Some(random_game) = roll::take_random_game(&mut owned_games, &cooldown, roller.rng()) {
+            let random_achievement: Option<GameAchievement> = goals::get_random_achievement_for_game(&credentials.key, &credentials.steam_id, &random_game, roller.rng()).await;
             if let Some(a) = random_achievement {
                 game_and_achievement = Some((random_game, a));
                 break;
@@ -111,47 +398,223 @@ async fn main() -> Result<(), reqwest::Error> {
                         );
                 
                 // Save the achievement
-                achievement_store::save_achievement(&g_a.1.name, &g_a.1.display_name, &g_a.1.description, &g_a.0.appid, &g_a.0.last_played).expect("Failed to save achievement");
-                println!("Saved the achievement!");
+                achievement_store::save_achievement(&g_a.1.name, &g_a.1.display_name, &g_a.1.description, &g_a.0.appid, &g_a.0.last_played, &Some(roller.stored_seed())).expect("Failed to save achievement");
+                cooldown.record(&g_a.0);
+                if cooldown.is_none() {
+                    println!("Saved the achievement! Roll again with --seed {seed} to get the same pick", seed = roller.seed());
+                }
+                else {
+                    println!("Saved the achievement! Recently picked games were less likely, --seed {seed} ignores them so the roll can be shared", seed = roller.seed());
+                }
             },
             None => println!("No games left with any achievements")
         }
     }
     else if args.goals {
         let credentials = get_credentials(&args);
-        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        let owned_games_vec = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::goal_sync(&owned_games_vec))) {
+            return Ok(());
+        }
+        let owned_games: HashMap<i32, game_fetch::Game> = owned_games_vec.iter().map(|n| (n.appid, n.clone())).collect();
         // Print all completed achievements!
         let completed_achievement = goals::get_and_sync_completed_achievements(&credentials.key, &credentials.steam_id).await;
         for ca in completed_achievement {
-            println!("Well done! You completed {game} : {name}", game = owned_games.get(&ca.app_id).expect("Achievement completed for unowned game?!?").name, name = ca.display_name);
+            println!("Well done! You completed {game} : {name}", game = game_name(&owned_games, &ca.app_id), name = ca.display_name);
         }
-        let mut achievements: Vec<achievement_store::Achievement> = achievement_store::get_achievements().expect("Failed to load achievements");
-        achievements.sort_by(|a, b| i32::cmp(&a.app_id,&b.app_id));
+        let today = Local::now().date_naive();
+        let achievements: Vec<achievement_store::Achievement> = achievement_store::get_achievements().expect("Failed to load achievements")
+            .into_iter()
+            .filter(|a| !args.overdue || a.is_overdue(today))
+            .filter(|a| args.due_soon.is_none_or(|days| a.is_due_within(today, days)))
+            .collect();
+        let notes: HashMap<(i32, Option<String>), String> = note_store::get_notes().expect("Failed to load notes")
+            .into_iter()
+            .map(|n| ((n.app_id, n.achievement_name), n.note))
+            .collect();
         
         for a in achievements {
+            let mut schedule: Vec<String> = Vec::new();
+            if a.priority != 0 {
+                schedule.push(format!("priority {}", a.priority));
+            }
+            if let Some(due) = a.due_date {
+                schedule.push(if a.is_overdue(today) { format!("overdue since {due}") } else { format!("due {due}") });
+            }
+            if let Some(seed) = a.seed {
+                schedule.push(format!("seed {}", seed as u64));
+            }
+            let schedule = if schedule.is_empty() { String::new() } else { format!(" ({})", schedule.join(", ")) };
             if a.description.is_none() {
-                println!("{game} : {name} [{id}]", name = a.display_name, game = owned_games.get(&a.app_id).expect("Achievement completed for unowned game?!?").name, id = a.id);
+                println!("{game} : {name} [{id}]{schedule}", name = a.display_name, game = game_name(&owned_games, &a.app_id), id = a.id);
             }
             else{
-                println!("{game} : {name} - {description} [{id}]", name = a.display_name, game = owned_games.get(&a.app_id).expect("Achievement completed for unowned game?!?").name, description = a.description.clone().unwrap(), id = a.id);
+                println!("{game} : {name} - {description} [{id}]{schedule}", name = a.display_name, game = game_name(&owned_games, &a.app_id), description = a.description.clone().unwrap(), id = a.id);
+            }
+            if let Some(note) = notes.get(&(a.app_id, None)) {
+                println!("    Game note: {note}");
+            }
+            if let Some(note) = notes.get(&(a.app_id, Some(a.achievement_name.clone()))) {
+                println!("    Note: {note}");
             }
         }
     }
     else if args.exclude_achievement.is_some() {
-        let achievement = achievement_store::get_achievement(&args.exclude_achievement.unwrap()).expect("Achievement no found");
+        let credentials = get_credentials(&args);
+        let achievement = achievement_store::get_achievement(&args.exclude_achievement.unwrap()).expect("Failed to load achievement").expect("Achievement not found");
         // First delete the achievement, if this is all that succeeds then it is at least off the list
         achievement_store::delete_achievement(&args.exclude_achievement.unwrap()).expect("Failed to delete achievement");
         // Add it to the list of excluded achievements
-        excluded_achievement_store::save_excluded_achievement(&achievement.achievement_name, &achievement.app_id).expect("Failed to save the exclusion");
+        excluded_achievement_store::save_excluded_achievement(&achievement.achievement_name, &achievement.app_id, &args.exclusion_reason).expect("Failed to save the exclusion");
+        refresh_game_completion(&credentials, &achievement.app_id).await;
+    }
+    else if let Some(id) = args.skip_achievement {
+        let (goal, available_after) = goals::skip_goal(&id).expect("Failed to skip the goal");
+        println!("Skipped {name}, it can be rolled again after {available_after}", name = goal.display_name);
+    }
+    else if let Some(days) = args.skip_cooldown {
+        settings::set_skip_cooldown_days(days).expect("Failed to save the skip cooldown");
+        println!("Skipped achievements will be left out of random rolls for {days} days");
+    }
+    else if let Some(days) = args.pick_cooldown {
+        settings::set_pick_cooldown_days(days).expect("Failed to save the pick cooldown");
+        println!("Games picked or played in the last {days} days will be less likely to be rolled");
+    }
+    else if let Some(limit) = args.goal_limit {
+        settings::set_goal_limit(limit).expect("Failed to save the goal limit");
+        if limit == 0 {
+            println!("Removed the goal limit");
+        }
+        else {
+            println!("At most {limit} goals can be active at once");
+        }
+    }
+    else if let Some(limit) = args.game_goal_limit {
+        settings::set_game_goal_limit(limit).expect("Failed to save the goal limit per game");
+        if limit == 0 {
+            println!("Removed the goal limit per game");
+        }
+        else {
+            println!("At most {limit} goals can be active at once for a game");
+        }
+    }
+    else if let Some(autopilot) = args.autopilot {
+        settings::set_autopilot(autopilot).expect("Failed to save the autopilot");
+        println!("Autopilot set to {autopilot}");
+    }
+    else if args.skips {
+        let credentials = get_credentials(&args);
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        let today = Local::now().date_naive();
+        for s in skipped_achievement_store::get_skipped_achievements().expect("Failed to load skipped achievements") {
+            let cooldown = if s.on_cooldown(today) { format!("available after {}", s.available_after) } else { "available".to_string() };
+            println!("{skipped_on} {game} : {name} ({cooldown})", skipped_on = s.skipped_on, game = game_name(&owned_games, &s.app_id), name = s.display_name);
+        }
+    }
+    else if let Some(id) = args.set_priority {
+        let achievement = achievement_store::get_achievement(&id).expect("Failed to load achievement").expect("Achievement not found");
+        achievement_store::update_priority(&id, &args.priority).expect("Failed to save the priority");
+        println!("Set the priority of {name} to {priority}", name = achievement.display_name, priority = args.priority);
+    }
+    else if let Some(id) = args.set_due {
+        let achievement = achievement_store::get_achievement(&id).expect("Failed to load achievement").expect("Achievement not found");
+        achievement_store::update_due_date(&id, &args.due).expect("Failed to save the due date");
+        if let Some(due) = args.due {
+            println!("{name} is due {due}", name = achievement.display_name);
+        }
+        else {
+            println!("Cleared the due date of {name}", name = achievement.display_name);
+        }
+    }
+    else if args.exclusions {
+        let credentials = get_credentials(&args);
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        let exclusions = excluded_achievement_store::get_excluded_achievements().expect("Failed to load exclusions");
+        for e in exclusions {
+            let reason = e.reason.map(|r| r.to_string()).unwrap_or("no reason".to_string());
+            println!("{game} : {name} ({reason}) [{id}]", game = game_name(&owned_games, &e.app_id), name = e.achievement_name, reason = reason, id = e.id);
+        }
+    }
+    else if let Some(id) = args.update_exclusion {
+        excluded_achievement_store::get_excluded_achievement(&id).expect("Failed to load exclusion").expect("Exclusion not found");
+        excluded_achievement_store::update_excluded_achievement_reason(&id, &args.exclusion_reason).expect("Failed to update the exclusion");
+        println!("Updated the exclusion!");
+    }
+    else if let Some(id) = args.remove_exclusion {
+        let credentials = get_credentials(&args);
+        let exclusion = excluded_achievement_store::get_excluded_achievement(&id).expect("Failed to load exclusion").expect("Exclusion not found");
+        excluded_achievement_store::delete_excluded_achievement(&id).expect("Failed to remove the exclusion");
+        println!("Removed the exclusion for {name}", name = exclusion.achievement_name);
+        refresh_game_completion(&credentials, &exclusion.app_id).await;
+    }
+    else if args.remove_game_exclusions {
+        let credentials = get_credentials(&args);
+        let game = request_game_name(&credentials.key, &credentials.steam_id, &None).await.expect("No game found for search");
+        excluded_achievement_store::delete_excluded_achievements_for_app(&game.appid).expect("Failed to remove the exclusions");
+        println!("Removed all exclusions for {name}", name = game.name);
+        if let Err(e) = goals::refresh_game_completion_for_app(&credentials.key, &credentials.steam_id, &game).await {
+            println!("Failed to refresh the completion of {name}: {e}", name = game.name);
+        }
+    }
+    else if let Some(kind) = args.exclusion_rule {
+        let credentials = get_credentials(&args);
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        let matches = match exclusion_rules::preview(&kind, &args.rule_value) {
+            Ok(m) => m,
+            Err(e) => {
+                println!("{e}");
+                return Ok(());
+            },
+        };
+        for a in &matches {
+            println!("{game} : {name}", game = game_name(&owned_games, &a.app_id), name = a.display_name);
+        }
+        let games = matches.iter().map(|a| a.app_id).collect::<HashSet<i32>>().len();
+        println!("The rule matches {count} achievements in {games} games with a cached schema, build more with --build-search-index", count = matches.len());
+        if args.save_rule {
+            let id = exclusion_rules::save_rule(&kind, &args.rule_value, &args.exclusion_reason).expect("Failed to save the exclusion rule");
+            println!("Saved the exclusion rule [{id}], completion is recalculated the next time it is refreshed");
+        }
+        else {
+            println!("Save it with --save-rule");
+        }
+    }
+    else if args.exclusion_rules {
+        for r in exclusion_rule_store::get_exclusion_rules().expect("Failed to load exclusion rules") {
+            let reason = r.reason.map(|r| r.to_string()).unwrap_or("no reason".to_string());
+            let rule = match r.kind {
+                RuleKind::Pattern => format!("Matches {}", r.value),
+                RuleKind::Rarity => format!("Fewer than {}% of players", r.value),
+                RuleKind::Hidden => "Hidden achievements".to_string(),
+                RuleKind::App => format!("Every achievement in {}", r.value),
+            };
+            println!("{rule} ({reason}) [{id}]", id = r.id);
+        }
+    }
+    else if let Some(id) = args.delete_exclusion_rule {
+        let rule = exclusion_rules::delete_rule(&id).expect("Failed to delete the exclusion rule");
+        println!("Deleted the {kind} exclusion rule, completion is recalculated the next time it is refreshed", kind = rule.kind.to_string().to_lowercase());
     }
     else if args.completed_games {
         // Get full game list
         let credentials = get_credentials(&args);
         let games: Vec<game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
-        goals::refresh_game_completion_cache(&credentials.key, &credentials.steam_id, &games).await;
-        let completed_games: Vec<game_completion_cache::GameCompletion> = game_completion_cache::get_game_completion_above_or_equal(100).expect("Failed to load completed games");
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::completion_refresh(&games))) {
+            return Ok(());
+        }
+        if !refresh_with_progress(&credentials, &games).await {
+            return Ok(());
+        }
+        let mut completed_games: Vec<GameProgress> = completion::get_game_progress(completion_policy(&args))
+            .into_values()
+            .filter(|g| g.complete && g.completion.has_achievements)
+            .collect();
+        completed_games.sort_by_key(|g| g.completion.app_id);
         for g in completed_games {
-            let game = games.iter().find(|game| game.appid == g.app_id).unwrap();
+            // Cached completion of games no longer owned is left for the doctor to report
+            let Some(game) = games.iter().find(|game| game.appid == g.completion.app_id) else {
+                continue;
+            };
             println!("Completed game: {name}", name = game.name);
         }
     }
@@ -159,12 +622,350 @@ async fn main() -> Result<(), reqwest::Error> {
         // Get full game list
         let credentials = get_credentials(&args);
         let games: Vec<game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
-        goals::refresh_game_completion_cache(&credentials.key, &credentials.steam_id, &games).await;
-        let progressed_games: Vec<game_completion_cache::GameCompletion> = game_completion_cache::get_game_completion_above_or_equal(1).expect("Failed to load completed games");
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::completion_refresh(&games))) {
+            return Ok(());
+        }
+        if !refresh_with_progress(&credentials, &games).await {
+            return Ok(());
+        }
+        let mut progressed_games: Vec<GameProgress> = completion::get_game_progress(completion_policy(&args))
+            .into_values()
+            .filter(|g| g.progress >= 1 && !g.complete && g.completion.has_achievements)
+            .collect();
+        progressed_games.sort_by_key(|g| std::cmp::Reverse(g.progress));
+        let estimates = time_estimate::get_estimates(&games, completion_policy(&args));
         for g in progressed_games {
-            if g.complete != 100 {
-                let game = games.iter().find(|game| game.appid == g.app_id).unwrap();
-                println!("{name} : {progress}", name = game.name, progress = g.complete);
+            let Some(game) = games.iter().find(|game| game.appid == g.completion.app_id) else {
+                continue;
+            };
+            let time_left = estimates.get(&game.appid).map(|e| e.display()).unwrap_or("-".to_string());
+            println!("{name} : {progress} [{counts}] {time_left} left", name = game.name, progress = g.progress, counts = g.display());
+        }
+    }
+    else if args.completion_policy {
+        let mut policy = settings::get_completion_policy();
+        if args.completion_formula.is_some() || args.completion_threshold.is_some() || args.targets_perfect.is_some() {
+            policy.formula = args.completion_formula.unwrap_or(policy.formula);
+            policy.complete_threshold = args.completion_threshold.unwrap_or(policy.complete_threshold);
+            policy.targets_perfect = args.targets_perfect.unwrap_or(policy.targets_perfect);
+            settings::set_completion_policy(&policy).expect("Failed to save the completion policy");
+            println!("Saved the completion policy");
+        }
+        println!("Formula: {formula}", formula = policy.formula);
+        println!("Complete at: {threshold}%", threshold = policy.complete_threshold);
+        println!("Targets marked complete are perfect: {perfect}", perfect = if policy.targets_perfect { "yes" } else { "only without achievements" });
+    }
+    else if args.scores {
+        let credentials = get_credentials(&args);
+        let games: Vec<game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::completion_refresh(&games) + estimate::scores())) {
+            return Ok(());
+        }
+        if !refresh_with_progress(&credentials, &games).await {
+            return Ok(());
+        }
+        score::fetch_missing_rarity().await.expect("Failed to fetch achievement rarity");
+        let scores = score::get_scores().expect("Failed to load scores");
+        let mut game_scores: Vec<&score::GameScore> = scores.games.values()
+            .filter(|g| g.points > 0)
+            .collect();
+        game_scores.sort_by_key(|g| std::cmp::Reverse(g.points));
+        for g in game_scores {
+            let name = games.iter().find(|game| game.appid == g.app_id).map(|game| game.name.clone()).unwrap_or(g.app_id.to_string());
+            println!("{name} : {points} [{unlocked} unlocked, {month} this month]", points = g.points, unlocked = g.unlocked, month = g.points_this_month);
+        }
+        println!("Total score: {total}", total = scores.total);
+        println!("Gained this month: {month}", month = scores.this_month);
+        if scores.unrated > 0 {
+            println!("{unrated} unlocked achievements have no rarity from Steam and score nothing", unrated = scores.unrated);
+        }
+    }
+    else if args.tags {
+        let credentials = get_credentials(&args);
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        let collection_games = collection_store::get_collection_games().expect("Failed to load tagged games");
+        for c in collection_store::get_collections().expect("Failed to load tags") {
+            let games: Vec<String> = collection_games.iter()
+                .filter(|g| g.collection_id == c.id)
+                .map(|g| game_name(&owned_games, &g.app_id))
+                .collect();
+            println!("{tag} : {games}", tag = c.name, games = games.join(", "));
+        }
+    }
+    else if args.tag_game || args.untag_game || args.delete_tag {
+        let tag = args.tag.clone().expect("Use --tag to give the tag name");
+        if args.delete_tag {
+            let collection = collection_store::get_collection(&tag).expect("Failed to load tag").expect("Tag not found");
+            collection_store::delete_collection(&collection.id).expect("Failed to delete tag");
+            println!("Deleted the tag {tag}");
+        }
+        else {
+            let credentials = get_credentials(&args);
+            let game = request_game_name(&credentials.key, &credentials.steam_id, &None).await.expect("No game found for search");
+            if args.tag_game {
+                let collection_id = collection_store::save_collection(&tag).expect("Failed to save tag");
+                collection_store::add_game_to_collection(&collection_id, &game.appid).expect("Failed to tag game");
+                println!("Tagged {name} with {tag}", name = game.name);
+            }
+            else {
+                let collection = collection_store::get_collection(&tag).expect("Failed to load tag").expect("Tag not found");
+                collection_store::remove_game_from_collection(&collection.id, &game.appid).expect("Failed to untag game");
+                println!("Removed {tag} from {name}", name = game.name);
+            }
+        }
+    }
+    else if let Some(note) = &args.note {
+        if let Some(goal_id) = args.note_goal {
+            let achievement = achievement_store::get_achievement(&goal_id).expect("Failed to load achievement").expect("Achievement not found");
+            note_store::save_note(&achievement.app_id, &Some(achievement.achievement_name), note).expect("Failed to save note");
+            println!("Saved the note for {name}", name = achievement.display_name);
+        }
+        else {
+            let credentials = get_credentials(&args);
+            let game = request_game_name(&credentials.key, &credentials.steam_id, &None).await.expect("No game found for search");
+            note_store::save_note(&game.appid, &None, note).expect("Failed to save note");
+            println!("Saved the note for {name}", name = game.name);
+        }
+    }
+    else if args.notes || args.search_notes.is_some() {
+        let credentials = get_credentials(&args);
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        let notes = if let Some(query) = &args.search_notes {
+            note_store::search_notes(query).expect("Failed to search notes")
+        }
+        else {
+            note_store::get_notes().expect("Failed to load notes")
+        };
+        for n in notes {
+            if let Some(achievement_name) = n.achievement_name {
+                println!("{game} : {achievement_name} - {note}", game = game_name(&owned_games, &n.app_id), note = n.note);
+            }
+            else {
+                println!("{game} - {note}", game = game_name(&owned_games, &n.app_id), note = n.note);
+            }
+        }
+    }
+    else if args.progress_history {
+        for s in progress_snapshot_store::get_progress_snapshots().expect("Failed to load progress snapshots") {
+            println!(
+                "{date} : {unlocked} achievements, {completed} completed, {perfected} perfected, {average:.1}% average completion, {requests} requests",
+                date = s.date,
+                unlocked = s.achievements_unlocked,
+                completed = s.games_completed,
+                perfected = s.games_perfected,
+                average = s.average_completion,
+                requests = s.request_count
+            );
+        }
+    }
+    else if args.daily {
+        let credentials = get_credentials(&args);
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::daily_challenge())) {
+            return Ok(());
+        }
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        match daily::get_daily_challenge(&credentials.key, &credentials.steam_id).await {
+            Ok(Some(c)) => {
+                println!("Today's challenge is:");
+                println!(
+                    "{game} : {name} - {description}",
+                    game = game_name(&owned_games, &c.app_id),
+                    name = c.display_name,
+                    description = c.description.unwrap_or("no description".to_string())
+                );
+                println!("{status}", status = if c.completed { "Completed!" } else { "Not completed yet" });
+            },
+            Ok(None) => println!("No games with progress have achievements left for a challenge"),
+            Err(e) => println!("{e}"),
+        }
+        println!("Streak: {streak} days", streak = daily::streak(Local::now().date_naive()));
+    }
+    else if let Some(seed) = args.daily_seed {
+        settings::set_daily_challenge_seed(seed).expect("Failed to save the daily challenge seed");
+        println!("Daily challenges not picked yet will use the seed {seed}");
+    }
+    else if let Some(size) = args.bingo {
+        let credentials = get_credentials(&args);
+        let games = goals::filter_to_collection(game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await, &args.tag);
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::bingo(size, args.balance_rarity))) {
+            return Ok(());
+        }
+        let owned_games: HashMap<i32, game_fetch::Game> = games.iter().map(|n| (n.appid, n.clone())).collect();
+        let mut roller = Roller::new(args.seed);
+        match bingo::generate_card(&credentials.key, &credentials.steam_id, &games, size, args.balance_rarity, &mut roller).await {
+            Ok(card) => {
+                print!("{}", card.render(|app_id| game_name(&owned_games, app_id)));
+                if roll::Cooldown::for_roller(&roller).is_none() {
+                    println!("Saved bingo card [{id}], the squares are added to your goals. Generate it again with --seed {seed}", id = card.set.id, seed = roller.seed());
+                }
+                else {
+                    println!("Saved bingo card [{id}], the squares are added to your goals. Recently picked games were less likely, --seed {seed} ignores them so the card can be shared", id = card.set.id, seed = roller.seed());
+                }
+            },
+            Err(e) => println!("{e}"),
+        }
+    }
+    else if let Some(id) = args.bingo_card {
+        let credentials = get_credentials(&args);
+        let owned_games_vec = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::goal_sync(&owned_games_vec))) {
+            return Ok(());
+        }
+        // Syncing the goals marks any squares that have been unlocked
+        goals::get_and_sync_completed_achievements(&credentials.key, &credentials.steam_id).await;
+        let owned_games: HashMap<i32, game_fetch::Game> = owned_games_vec.iter().map(|n| (n.appid, n.clone())).collect();
+        let card = match id {
+            Some(id) => bingo::get_card(&id),
+            None => bingo::get_latest_card(),
+        };
+        if let Some(card) = card {
+            println!("{name} [{id}]", name = card.set.name, id = card.set.id);
+            print!("{}", card.render(|app_id| game_name(&owned_games, app_id)));
+            println!("{done}/{total} squares, {lines} lines", done = card.items.iter().filter(|i| i.completed).count(), total = card.items.len(), lines = card.completed_lines());
+        }
+        else {
+            println!("No bingo card found, generate one with --bingo");
+        }
+    }
+    else if let Some(count) = args.multi_roll {
+        let credentials = get_credentials(&args);
+        let games = goals::filter_to_collection(game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await, &args.tag);
+        let constraints = multi_roll::RollConstraints {
+            max_active_goals: args.max_goals,
+            min_playtime: args.min_playtime.map(|h| h * 60),
+            max_playtime: args.max_playtime.map(|h| h * 60),
+            min_rarity: args.min_rarity,
+            max_rarity: args.max_rarity,
+        };
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::multi_roll(count, constraints.rarity_rule() != RarityRule::Any))) {
+            return Ok(());
+        }
+        let owned_games: HashMap<i32, game_fetch::Game> = games.iter().map(|n| (n.appid, n.clone())).collect();
+        let mut roller = Roller::new(args.seed);
+        match multi_roll::roll_goals(&credentials.key, &credentials.steam_id, &games, count, &constraints, &mut roller).await {
+            Ok(roll) => {
+                for i in &roll.items {
+                    let rarity = i.rarity.map(|r| format!(" ({r:.1}% of players)")).unwrap_or_default();
+                    println!("{game} : {name} - {description}{rarity}", game = game_name(&owned_games, &i.app_id), name = i.display_name, description = i.description.clone().unwrap_or_default());
+                }
+                if roll::Cooldown::for_roller(&roller).is_none() {
+                    println!("Saved {name} [{id}] with {count} goals. Roll again with --seed {seed} to get the same picks", name = roll.set.name, id = roll.set.id, seed = roller.seed());
+                }
+                else {
+                    println!("Saved {name} [{id}] with {count} goals. Recently picked games were less likely, --seed {seed} ignores them so the roll can be shared", name = roll.set.name, id = roll.set.id, seed = roller.seed());
+                }
+            },
+            Err(e) => println!("{e}"),
+        }
+    }
+    else if args.schema_changes {
+        let credentials = get_credentials(&args);
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        for c in schema_change_store::get_schema_changes().expect("Failed to load schema changes") {
+            println!("[{id}] {date} : {game} gained {added} achievements ({previous} -> {new})",
+                id = c.id, date = c.detected_on, game = game_name(&owned_games, &c.app_id), added = c.added(), previous = c.previous_total, new = c.new_total);
+        }
+    }
+    else if let Some(id) = args.dismiss_schema_change {
+        schema_change_store::delete_schema_change(&id).expect("Failed to dismiss the schema change");
+        println!("Dismissed the schema change!");
+    }
+    else if let Some(query) = &args.search {
+        let credentials = get_credentials(&args);
+        let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::search(query, args.unachieved))) {
+            return Ok(());
+        }
+        let results = search::search(&credentials.key, &credentials.steam_id, query, args.unachieved).await.expect("Failed to search achievements");
+        if results.is_empty() {
+            println!("No achievements found, use --build-search-index to search more games");
+        }
+        for r in results {
+            let goal = if r.is_goal { " (goal)" } else { "" };
+            if let Some(description) = r.description {
+                println!("{game} : {name} - {description}{goal}", game = game_name(&owned_games, &r.app_id), name = r.display_name);
+            }
+            else {
+                println!("{game} : {name}{goal}", game = game_name(&owned_games, &r.app_id), name = r.display_name);
+            }
+        }
+    }
+    else if args.build_search_index {
+        let credentials = get_credentials(&args);
+        let owned_games = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
+        if !preflight(&args, &estimate::RequestEstimate::new(estimate::schema_index(&owned_games))) {
+            return Ok(());
+        }
+        let fetched = search::index_schemas(&credentials.key, &owned_games).await;
+        println!("Added {fetched} games to the search index");
+    }
+    else if args.quota || args.request_quota.is_some() || args.request_timezone.is_some() {
+        if let Some(quota) = args.request_quota {
+            request_store::save_quota(&quota).expect("Failed to save the request quota");
+        }
+        if let Some(timezone) = args.request_timezone {
+            request_store::save_timezone(&timezone).expect("Failed to save the request timezone");
+        }
+        let timezone = request_store::get_timezone().expect("Failed to load the request timezone");
+        println!(
+            "{count} of {quota} requests used on {today} ({timezone})",
+            count = request_store::get_count().expect("Failed to load request count"),
+            quota = request_store::get_quota().expect("Failed to load the request quota"),
+            today = timezone.today()
+        );
+        for u in request_store::get_usage_by_endpoint().expect("Failed to load request usage") {
+            println!(
+                "{endpoint} : {count} requests, {failures} failed, {latency:.0}ms average",
+                endpoint = u.endpoint,
+                count = u.count,
+                failures = u.failures,
+                latency = u.average_latency_ms
+            );
+        }
+    }
+    else if let Some(path) = &args.export {
+        backup::export_to_file(path).expect("Failed to export");
+        println!("Exported to {path}");
+    }
+    else if let Some(path) = &args.import_hours_to_beat {
+        let count = time_estimate::import_hours_to_beat(path).expect("Failed to import hours to beat");
+        println!("Imported the hours to beat for {count} games");
+    }
+    else if let Some(path) = &args.import {
+        let summary = backup::import_from_file(path, args.import_mode).expect("Failed to import");
+        println!(
+            "Imported {goals} goals, {exclusions} exclusions, {targets} targets, {tags} tags, {notes} notes, {skips} skips, {settings} settings, {snapshots} snapshots, {daily_challenges} daily challenges, {goal_sets} goal sets, {exclusion_rules} exclusion rules and {hours_to_beat} hours to beat",
+            goals = summary.goals,
+            exclusions = summary.exclusions,
+            targets = summary.targets,
+            tags = summary.collections,
+            notes = summary.notes,
+            skips = summary.skips,
+            settings = summary.settings,
+            snapshots = summary.snapshots,
+            daily_challenges = summary.daily_challenges,
+            goal_sets = summary.goal_sets,
+            exclusion_rules = summary.exclusion_rules,
+            hours_to_beat = summary.hours_to_beat
+        );
+        if summary.steam_id {
+            println!("Imported the steam id");
+        }
+    }
+    else if args.doctor {
+        let credentials = get_credentials(&args);
+        let problems = doctor::diagnose(&credentials.key, &credentials.steam_id).await;
+        if problems.is_empty() {
+            println!("No problems found");
+        }
+        for p in problems {
+            println!("{p}");
+            if args.fix && p.can_fix() && confirm("Fix this? [y/N]") {
+                doctor::fix(&p).expect("Failed to fix the problem");
+                println!("Fixed!");
+            }
+            else if args.fix && !p.can_fix() {
+                println!("{error}", error = doctor::fix(&p).unwrap_err());
             }
         }
     }
@@ -199,7 +1000,104 @@ fn get_credentials(args: &Args) -> Credentials {
     Credentials { key, steam_id }
 }
 
-async fn request_game_name(key : &str, steam_id : &str) -> Option<game_fetch::Game> {
+fn confirm(question: &str) -> bool {
+    let mut answer = String::new();
+    println!("{question}");
+    io::stdin()
+        .read_line(&mut answer)
+        .expect("Failed to read line");
+    answer.trim().eq_ignore_ascii_case("y")
+}
+
+/// The saved completion policy with any formula given for this command
+fn completion_policy(args: &Args) -> CompletionPolicy {
+    let mut policy = settings::get_completion_policy();
+    if let Some(formula) = args.completion_formula {
+        policy.formula = formula;
+    }
+    policy
+}
+
+fn game_name(owned_games: &HashMap<i32, game_fetch::Game>, app_id: &i32) -> String {
+    owned_games.get(app_id).map(|g| g.name.clone()).unwrap_or(format!("Unowned game {app_id}"))
+}
+
+async fn refresh_game_completion(credentials: &Credentials, app_id: &i32) {
+    let owned_games: Vec<game_fetch::Game> = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await;
+    if let Some(game) = owned_games.iter().find(|g| g.appid == *app_id) {
+        if let Err(e) = goals::refresh_game_completion_for_app(&credentials.key, &credentials.steam_id, game).await {
+            println!("Failed to refresh the completion of {name}: {e}", name = game.name);
+        }
+    }
+}
+
+/// Refresh the completion cache showing a progress bar, Ctrl-C stops after the current game.
+/// Returns false if the refresh did not finish, the next run resumes where it stopped
+async fn refresh_with_progress(credentials: &Credentials, games: &[game_fetch::Game]) -> bool {
+    let cancel = Arc::new(AtomicBool::new(false));
+    let cancel_signal = cancel.clone();
+    let signal = tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            cancel_signal.store(true, Ordering::Relaxed);
+        }
+    });
+    let seen_changes: Vec<i32> = schema_change_store::get_schema_changes().expect("Failed to load schema changes")
+        .iter()
+        .map(|c| c.id)
+        .collect();
+    let mut progress = (0, 0);
+    let result = goals::refresh_game_completion_cache_with_progress(&credentials.key, &credentials.steam_id, games, |p| {
+        if p.done == 0 && p.resumed > 0 {
+            println!("Resuming the last refresh, {resumed} games left from it", resumed = p.resumed);
+        }
+        progress = (p.done, p.total);
+        const WIDTH: usize = 30;
+        if let Some(filled) = (p.done * WIDTH).checked_div(p.total) {
+            print!("\rRefreshing [{bar}{space}] {done}/{total}", bar = "#".repeat(filled), space = " ".repeat(WIDTH - filled), done = p.done, total = p.total);
+            io::stdout().flush().expect("Failed to flush output");
+        }
+    }, &cancel).await;
+    signal.abort();
+    if progress.1 > 0 {
+        println!();
+    }
+    for c in schema_change_store::get_schema_changes().expect("Failed to load schema changes") {
+        if !seen_changes.contains(&c.id) {
+            let name = games.iter().find(|g| g.appid == c.app_id).map(|g| g.name.clone()).unwrap_or(c.app_id.to_string());
+            println!("New achievements added to {name}: {previous} -> {new}", previous = c.previous_total, new = c.new_total);
+        }
+    }
+    match result {
+        Ok(goals::RefreshOutcome::Completed) => true,
+        Ok(goals::RefreshOutcome::Cancelled) => {
+            println!("Stopped after {done} of {total} games, run again to resume", done = progress.0, total = progress.1);
+            false
+        }
+        Err(e) => {
+            println!("{e}");
+            println!("Stopped after {done} of {total} games, run again to resume", done = progress.0, total = progress.1);
+            false
+        }
+    }
+}
+
+/// Print the estimate for --dry-run, otherwise stop when there isn't enough of the request quota left.
+/// Returns true when the command should go ahead
+fn preflight(args: &Args, estimate: &estimate::RequestEstimate) -> bool {
+    if args.dry_run {
+        println!("{estimate}");
+        false
+    }
+    else if let Err(e) = estimate.check() {
+        println!("{e}");
+        false
+    }
+    else {
+        true
+    }
+}
+
+async fn request_game_name(key : &str, steam_id : &str, collection: &Option<String>) -> Option<game_fetch::Game> {
     let mut game_name= String::new();
     println!("Please enter the game name:");  
   
@@ -209,7 +1107,7 @@ async fn request_game_name(key : &str, steam_id : &str) -> Option<game_fetch::Ga
 	
     // Fetch games and search for it
     let game_name_lowercase: String = game_name.trim().to_lowercase();
-    let game_list: Vec<game_fetch::Game> = game_fetch::get_owned_games(key, steam_id).await
+    let game_list: Vec<game_fetch::Game> = goals::filter_to_collection(game_fetch::get_owned_games(key, steam_id).await, collection)
         .iter()
         .filter(|a| a.name.to_lowercase().contains(&game_name_lowercase))
         .cloned()
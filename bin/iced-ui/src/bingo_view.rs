@@ -0,0 +1,110 @@
+use super::App;
+
+use crate::{Credentials, Message, OWNED_GAMES};
+
+use iced::widget::{
+    text, center_x, column, row, button, checkbox, container, grid, image, image::Handle, pick_list, scrollable
+};
+use iced::Element;
+use goals_lib::{bingo, bingo::BingoCard, goals, roll::Roller};
+use std::collections::HashMap;
+
+use crate::game_view::{self, GoalState};
+
+pub const BINGO_SIZES: [usize; 3] = [3, 4, 5];
+
+#[derive(Debug, Clone)]
+pub struct BingoDisplay {
+    pub card: Option<BingoCard>,
+    pub error: Option<String>,
+}
+
+impl BingoDisplay {
+    /// Sync the goals first so squares that have been unlocked are marked
+    pub async fn load(credentials: Credentials) -> Self {
+        goals::get_and_sync_completed_achievements(&credentials.key, &credentials.steam_id).await;
+        BingoDisplay {
+            card: bingo::get_latest_card(),
+            error: None,
+        }
+    }
+
+    pub async fn generate(credentials: Credentials, size: usize, balance_rarity: bool, tag: Option<String>) -> Self {
+        let games = goals::filter_to_collection(OWNED_GAMES.values().cloned().collect(), &tag);
+        match bingo::generate_card(&credentials.key, &credentials.steam_id, &games, size, balance_rarity, &mut Roller::new(None)).await {
+            Ok(card) => BingoDisplay { card: Some(card), error: None },
+            Err(e) => BingoDisplay { card: bingo::get_latest_card(), error: Some(e) },
+        }
+    }
+}
+
+impl App {
+    pub fn bingo_view(&self) -> Element<'_, Message> {
+        let controls = row![
+            pick_list(BINGO_SIZES, Some(self.bingo_size), Message::BingoSizeSelected),
+            checkbox(self.bingo_balance_rarity)
+                .label("Balance Rarity")
+                .on_toggle(Message::BingoBalanceToggled),
+            button("New Card").on_press(Message::GenerateBingo),
+        ].spacing(5);
+
+        let main_view = if let Some(display) = &self.bingo {
+            let error = text(display.error.clone().unwrap_or_default()).style(text::danger);
+            if let Some(card) = &display.card {
+                let squares = card.items.iter().map(|i| {
+                    let game_name = OWNED_GAMES.get(&i.app_id).map(|g| g.name.clone()).unwrap_or(i.app_id.to_string());
+                    let icon: Element<'_, Message> = if let Some(h) = self.bingo_icons.get(&(i.app_id, i.achievement_name.clone(), i.completed)) {
+                        image(h).width(64).height(64).into()
+                    }
+                    else {
+                        text("").into()
+                    };
+                    let name = if i.completed { text(&i.display_name).style(text::success) } else { text(&i.display_name) };
+                    container(
+                        column![
+                            icon,
+                            name.size(14),
+                            button(text(game_name).size(12)).on_press(Message::GameView(i.app_id)),
+                        ].spacing(5)
+                    )
+                    .padding(5)
+                    .style(container::bordered_box)
+                    .into()
+                });
+                column![
+                    error,
+                    text(format!("{name}: {done}/{total} squares, {lines} lines",
+                        name = card.set.name,
+                        done = card.items.iter().filter(|i| i.completed).count(),
+                        total = card.items.len(),
+                        lines = card.completed_lines())),
+                    scrollable(grid(squares).columns(card.size()).spacing(5)),
+                ].spacing(10)
+            }
+            else {
+                column![error, text("No bingo card yet, generate one")]
+            }
+        }
+        else {
+            column![text("Loading bingo card...")]
+        };
+
+        column![
+            center_x(controls).padding(5),
+            center_x(main_view).padding(10),
+        ].into()
+    }
+}
+
+/// Completed squares use the coloured icon, the rest the grey one
+pub async fn load_bingo_icons(card: BingoCard) -> HashMap<(i32, String, bool), Handle> {
+    let mut map = HashMap::new();
+    for i in card.items {
+        let state = if i.completed { GoalState::Complete } else { GoalState::Goal };
+        if let Ok(r) = game_view::load_goal_icon(i.app_id, i.achievement_name, i.icon, i.icon_gray, state).await {
+            map.insert((r.0, r.1, i.completed), r.2);
+        }
+        // This drops the error, it will reload on a fresh request
+    }
+    map
+}
@@ -0,0 +1,74 @@
+use super::App;
+
+use crate::{Credentials, Message, OWNED_GAMES};
+
+use iced::widget::{
+    text, center_x, column, button
+};
+use iced::Element;
+use chrono::Local;
+use goals_lib::daily;
+
+#[derive(Debug, Clone)]
+pub struct DailyDisplay {
+    // DISPLAY
+    pub game_name: String,
+    pub display_name: String,
+    pub description: String,
+    pub completed: bool,
+    // DATA
+    pub app_id: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct DailyResult {
+    pub challenge: Result<Option<DailyDisplay>, String>, // None when nothing is left to pick
+    pub streak: i32,
+}
+
+impl DailyResult {
+    pub async fn load(credentials: Credentials) -> Self {
+        let challenge = daily::get_daily_challenge(&credentials.key, &credentials.steam_id).await
+            .map(|c| c.map(|c| DailyDisplay {
+                game_name: OWNED_GAMES.get(&c.app_id).map(|g| g.name.clone()).unwrap_or(c.app_id.to_string()),
+                display_name: c.display_name,
+                description: c.description.unwrap_or("-".to_string()),
+                completed: c.completed,
+                app_id: c.app_id,
+            }));
+        DailyResult {
+            challenge,
+            streak: daily::streak(Local::now().date_naive()),
+        }
+    }
+}
+
+impl App {
+    pub fn daily_view(&self) -> Element<'_, Message> {
+        let main_view = if let Some(daily) = &self.daily {
+            let challenge = match &daily.challenge {
+                Ok(Some(c)) => column![
+                    text("Today's challenge").size(24),
+                    button(c.game_name.as_str()).on_press(Message::GameView(c.app_id)),
+                    text(&c.display_name).size(20),
+                    text(&c.description),
+                    if c.completed { text("Completed!").style(text::success) } else { text("Not completed yet") },
+                ].spacing(10),
+                Ok(None) => column![text("No games with progress have achievements left for a challenge")],
+                Err(e) => column![text(e).style(text::danger)],
+            };
+            column![
+                challenge,
+                text(format!("Streak: {} days", daily.streak)),
+                button("Check again").on_press(Message::DailyView),
+            ].spacing(10)
+        }
+        else {
+            column![
+                text("Loading today's challenge...")
+            ]
+        };
+
+        center_x(main_view).padding(10).into()
+    }
+}
@@ -0,0 +1,89 @@
+use super::App;
+
+use crate::{Message, OWNED_GAMES};
+
+use iced::font;
+use iced::widget::{
+    table, text, center_x, center_y, column, scrollable, button, pick_list, row
+};
+use iced::{Center, Left, Font, Element};
+use db::{
+    excluded_achievement_store,
+    excluded_achievement_store::ExclusionReason,
+};
+
+#[derive(Debug, Clone)]
+pub struct Exclusion {
+    // DISPLAY
+    pub game_name: String,
+    pub achievement_name: String,
+    // DATA
+    pub id: i32,
+    pub app_id: i32,
+    pub reason: Option<ExclusionReason>,
+}
+
+impl Exclusion {
+    pub async fn list() -> Vec<Self> {
+        excluded_achievement_store::get_excluded_achievements().expect("Failed to load exclusions")
+            .iter()
+            .map(|e| Exclusion {
+                game_name: OWNED_GAMES.get(&e.app_id).map(|g| g.name.clone()).unwrap_or(e.app_id.to_string()),
+                achievement_name: e.achievement_name.clone(),
+                id: e.id,
+                app_id: e.app_id,
+                reason: e.reason,
+            })
+            .collect()
+    }
+}
+
+impl App {
+    pub fn exclusions_view(&self) -> Element<'_, Message> {
+        let main_view = if let Some(exclusions) = &self.exclusions {
+            let bold = |header| {
+                text(header).font(Font {
+                    weight: font::Weight::Bold,
+                    ..Font::DEFAULT
+                })
+            };
+            let columns = [
+                table::column(bold("Game Name"), |exclusion: &Exclusion| button(exclusion.game_name.as_str()).on_press(Message::GameView(exclusion.app_id)))
+                    .align_x(Left)
+                    .align_y(Center),
+                table::column(bold("Achievement Name"), |exclusion: &Exclusion| text(&exclusion.achievement_name))
+                    .align_x(Left)
+                    .align_y(Center),
+                table::column(bold("Reason"), |exclusion: &Exclusion| {
+                        let app_id = exclusion.app_id;
+                        let id = exclusion.id;
+                        row![
+                            pick_list(ExclusionReason::ALL, exclusion.reason, move |r| Message::UpdateExclusionReason(app_id, id, Some(r)))
+                                .placeholder("Reason"),
+                            button("Clear").on_press_maybe(exclusion.reason.map(|_| Message::UpdateExclusionReason(app_id, id, None))),
+                        ].spacing(5)
+                    })
+                    .align_x(Left)
+                    .align_y(Center),
+                table::column(bold("Remove"), |exclusion: &Exclusion| button("Include").on_press(Message::RemoveExclusion(exclusion.app_id, exclusion.id)))
+                    .align_x(Left)
+                    .align_y(Center),
+            ];
+
+            column![table(columns, exclusions)
+                .padding_x(10)
+                .padding_y(5)
+                .separator_x(1)
+                .separator_y(1)]
+        }
+        else {
+            column![
+                text("Loading")
+            ]
+        };
+
+        column![
+            center_y(scrollable(center_x(main_view)).spacing(10)).padding(10),
+        ].into()
+    }
+}
@@ -3,10 +3,11 @@ use super::App;
 use crate::View;
 use crate::Message;
 use crate::Credentials;
+use crate::OWNED_GAMES;
 
-use db::excluded_achievement_store;
+use db::{collection_store, note_store, excluded_achievement_store, excluded_achievement_store::ExclusionReason};
 use iced::widget::{
-    center_x, center_y, column, text, button, table, scrollable, image, image::Handle
+    center_x, center_y, column, row, text, button, table, scrollable, image, image::Handle, pick_list, text_input
 };
 use iced::{Center, Left, Element, Font, font};
 use api::{
@@ -15,13 +16,13 @@ use api::{
     game_fetch,
     game_fetch::Game,
 };
-use std::collections::{HashSet, HashMap};
+use std::collections::HashMap;
 use db::{
     game_target_store,
     achievement_store,
 };
 use rayon::prelude::*;
-use goals_lib::goals;
+use goals_lib::{goals, roll::Roller, settings, time_estimate};
 use simple_error::SimpleError;
 
 #[derive(Debug, Clone)]
@@ -30,7 +31,11 @@ pub struct GameDisplay {
     pub game_name: String,
     pub target: bool,
     pub complete: bool,
+    pub tags: Vec<String>,
+    pub note: String,
     pub goals: Vec<GameGoalDisplay>,
+    pub goal_limit: Option<String>, // Why no more goals can be rolled for the game
+    pub time_left: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq)]
@@ -51,6 +56,9 @@ pub struct GameGoalDisplay {
     pub achievement_name: String,
     pub icon: String,
     pub icon_gray: String,
+    pub exclusion_id: Option<i32>,
+    pub exclusion_reason: Option<ExclusionReason>,
+    pub note: String,
 }
 
 impl App {
@@ -69,7 +77,10 @@ impl App {
                             None
                         }
                     };
-                    let random_achievement = button("Random achievement!").on_press(Message::GenerateRandomAchievement(app_id));
+                    let random_achievement: Element<'_, Message> = match &game.goal_limit {
+                        Some(limit) => text(limit.clone()).into(),
+                        None => button("Random achievement!").on_press(Message::GenerateRandomAchievement(app_id)).into(),
+                    };
 
                     let controls = if let Some(target) = game_target_button {
                         column![
@@ -81,6 +92,21 @@ impl App {
                         column![random_achievement]
                     };
 
+                    let tags = {
+                        let mut tag_row = row![text("Tags:")].spacing(5);
+                        for t in &game.tags {
+                            tag_row = tag_row.push(button(text(format!("{t} x"))).on_press(Message::UntagGame(app_id, t.clone())));
+                        }
+                        tag_row
+                            .push(text_input("New tag...", &self.tag_input).on_input(Message::TagInput).width(150))
+                            .push(button("Add Tag").on_press(Message::TagGame(app_id)))
+                    };
+
+                    let game_note = text_input("Notes for this game, Enter saves...", &game.note)
+                        .on_input(move |n| Message::GameNoteChanged(app_id, n))
+                        .on_submit(Message::GameNoteSubmitted(app_id))
+                        .width(600);
+
                     let table = {
                         let bold = |header| {
                             text(header).font(Font {
@@ -114,7 +140,31 @@ impl App {
                             table::column(bold("Description"), |goal: &GameGoalDisplay| text(&goal.description))
                                 .align_x(Left)
                                 .align_y(Center),
-                            table::column(bold("Exclude"), |goal: &GameGoalDisplay| button("Exclude").on_press(Message::ExcludeAchievement(app_id, goal.achievement_name.clone())))
+                            table::column(bold("Notes"), |goal: &GameGoalDisplay| {
+                                    let achievement_name = goal.achievement_name.clone();
+                                    text_input("Add a note, Enter saves...", &goal.note)
+                                        .on_input({
+                                            let achievement_name = achievement_name.clone();
+                                            move |n| Message::GoalNoteChanged(app_id, achievement_name.clone(), n)
+                                        })
+                                        .on_submit(Message::GoalNoteSubmitted(app_id, achievement_name))
+                                        .width(250)
+                                })
+                                .align_x(Left)
+                                .align_y(Center),
+                            table::column(bold("Exclude"), |goal: &GameGoalDisplay| {
+                                    if let Some(exclusion_id) = goal.exclusion_id {
+                                        row![
+                                            pick_list(ExclusionReason::ALL, goal.exclusion_reason, move |r| Message::UpdateExclusionReason(app_id, exclusion_id, Some(r)))
+                                                .placeholder("Reason"),
+                                            button("Clear").on_press_maybe(goal.exclusion_reason.map(|_| Message::UpdateExclusionReason(app_id, exclusion_id, None))),
+                                            button("Include").on_press(Message::RemoveExclusion(app_id, exclusion_id)),
+                                        ].spacing(5)
+                                    }
+                                    else {
+                                        row![button("Exclude").on_press(Message::ExcludeAchievement(app_id, goal.achievement_name.clone()))]
+                                    }
+                                })
                                 .align_x(Left)
                                 .align_y(Center),
                         ];
@@ -128,7 +178,10 @@ impl App {
 
                     column![
                         center_x(text(game.game_name.clone())),
+                        center_x(text(format!("Estimated time left: {}", game.time_left.as_deref().unwrap_or("not enough to go on")))),
                         center_x(controls),
+                        center_x(tags).padding(5),
+                        center_x(game_note).padding(5),
                         center_y(scrollable(center_x(table)).spacing(10)).padding(10),
                     ].into()
                 }
@@ -142,9 +195,9 @@ impl App {
         }
     }
 
-    pub fn handle_generated_random_achievement(&mut self, game: Game, random_achievement: Option<GameAchievement>) {
+    pub fn handle_generated_random_achievement(&mut self, game: Game, random_achievement: Option<GameAchievement>, seed: i64) {
         if let Some(ra) = random_achievement {
-            achievement_store::save_achievement(&ra.name, &ra.display_name, &ra.description, &game.appid, &game.last_played).expect("Failed to save achievement");
+            achievement_store::save_achievement(&ra.name, &ra.display_name, &ra.description, &game.appid, &game.last_played, &Some(seed)).expect("Failed to save achievement");
             if let Some(game_view) = self.game_views.get_mut(&game.appid) {
                 if let Some(achievement) = game_view.goals.iter_mut().find(|a| a.achievement_name == ra.name) {
                     achievement.goal_state = GoalState::Goal;
@@ -154,9 +207,11 @@ impl App {
     }
 }
 
-pub async fn generate_random_achievement(credentials: Credentials, app_id: i32) -> Result<(Game, Option<GameAchievement>), SimpleError> {
+pub async fn generate_random_achievement(credentials: Credentials, app_id: i32) -> Result<(Game, Option<GameAchievement>, i64), SimpleError> {
     if let Some(game) = game_fetch::get_owned_games(&credentials.key, &credentials.steam_id).await.iter().find(|g| g.appid == app_id) {
-        Ok((game.clone(), goals::get_random_achievement_for_game(&credentials.key, &credentials.steam_id, game).await))
+        let mut roller = Roller::new(None);
+        let random_achievement = goals::get_random_achievement_for_game(&credentials.key, &credentials.steam_id, game, roller.rng()).await;
+        Ok((game.clone(), random_achievement, roller.stored_seed()))
     }
     else {
         Err(SimpleError::new("No game with that app_id"))
@@ -165,9 +220,13 @@ pub async fn generate_random_achievement(credentials: Credentials, app_id: i32)
 
 pub async fn load_game_display(credentials: Credentials, app_id: i32, game_name: String) -> GameDisplay {
     let player_achievements = achievement_fetch::get_player_achievements(&credentials.key, &credentials.steam_id, &app_id).await;   
-    let excluded_achievements: HashSet<String> = excluded_achievement_store::get_excluded_achievements_for_app(&app_id).expect("Failed to load excluded achievements")
+    let excluded_achievements: HashMap<String, (i32, Option<ExclusionReason>)> = excluded_achievement_store::get_excluded_achievements_for_app(&app_id).expect("Failed to load excluded achievements")
         .iter()
-        .map(|a| a.achievement_name.clone())
+        .map(|a| (a.achievement_name.clone(), (a.id, a.reason)))
+        .collect();
+    let notes: HashMap<Option<String>, String> = note_store::get_notes_for_app(&app_id).expect("Failed to load notes")
+        .into_iter()
+        .map(|n| (n.achievement_name, n.note))
         .collect();
 
     let mut goals: Vec<GameGoalDisplay> = achievement_fetch::get_game_achievements(&credentials.key, &app_id).await
@@ -182,7 +241,7 @@ pub async fn load_game_display(credentials: Credentials, app_id: i32, game_name:
                         .unwrap_or(false) {
                     GoalState::Complete
                 }
-                else if excluded_achievements.contains(&a.name) {
+                else if excluded_achievements.contains_key(&a.name) {
                     GoalState::Excluded
                 }
                 else if achievement_store::get_achievements_for_app(&app_id).expect("Failed to read achievement store").iter().any(|goal| goal.achievement_name == a.name) {
@@ -200,6 +259,9 @@ pub async fn load_game_display(credentials: Credentials, app_id: i32, game_name:
                 achievement_name: a.name.clone(),
                 icon: a.icon.clone(),
                 icon_gray: a.icongray.clone(),
+                exclusion_id: excluded_achievements.get(&a.name).map(|e| e.0),
+                exclusion_reason: excluded_achievements.get(&a.name).and_then(|e| e.1),
+                note: notes.get(&Some(a.name.clone())).cloned().unwrap_or_default(),
             }
         })
         .collect();
@@ -211,6 +273,15 @@ pub async fn load_game_display(credentials: Credentials, app_id: i32, game_name:
         goals,
         target: target.is_some(),
         complete: target.map(|t| t.complete).unwrap_or(false),
+        tags: collection_store::get_collections_for_app(&app_id).expect("Failed to load tags")
+            .into_iter()
+            .map(|c| c.name)
+            .collect(),
+        note: notes.get(&None).cloned().unwrap_or_default(),
+        goal_limit: goals::check_goal_limit(1).and_then(|_| goals::check_game_goal_limit(&app_id)).err(),
+        time_left: OWNED_GAMES.get(&app_id)
+            .and_then(|g| time_estimate::get_estimates(std::slice::from_ref(g), settings::get_completion_policy()).remove(&app_id))
+            .map(|e| e.display()),
     }
 }
 
@@ -4,14 +4,14 @@ use crate::{Message, OWNED_GAMES};
 
 use iced::font;
 use iced::widget::{
-    center_x, center_y, column, row, table, text, scrollable, button, checkbox, text_input
+    center_x, center_y, column, row, table, text, scrollable, button, checkbox, text_input, pick_list
 };
 use iced::{Element, Font};
 use db::{
-    game_completion_cache,
-    game_completion_cache::GameCompletion,
+    game_completion_cache::CompletionFormula,
     game_target_store,
 };
+use goals_lib::{completion, completion::{CompletionPolicy, GameProgress}, goals, score, score::GameScore, time_estimate, time_estimate::TimeEstimate};
 use api::{
     game_fetch::Game,
 };
@@ -33,6 +33,9 @@ pub struct GameListDisplay {
     //DISPLAY
     pub game_name: String,
     pub progress_display: String,
+    pub achievements_display: String,
+    pub score_display: String,
+    pub time_left_display: String,
     //DATA
     pub id: i32,
 }
@@ -45,11 +48,14 @@ pub struct GameListResult {
 }
 
 impl GameListDisplay {
-    pub async fn list(has_achievements: bool, filter: GameListFilter, title_search: Option<String>) -> GameListResult {
-        let completed_games_cache: HashMap<i32, GameCompletion> = game_completion_cache::get_game_completion()
-            .expect("Failed to load completed games")
+    pub async fn list(has_achievements: bool, filter: GameListFilter, title_search: Option<String>, policy: CompletionPolicy, tag: Option<String>) -> GameListResult {
+        let game_progress: HashMap<i32, GameProgress> = completion::get_game_progress(policy);
+        let game_scores: HashMap<i32, GameScore> = score::get_scores().expect("Failed to load scores").games;
+        let owned_games: Vec<Game> = OWNED_GAMES.values().cloned().collect();
+        let time_estimates: HashMap<i32, TimeEstimate> = time_estimate::get_estimates(&owned_games, policy);
+        let tagged_set: HashSet<i32> = goals::filter_to_collection(OWNED_GAMES.values().cloned().collect(), &tag)
             .iter()
-            .map(|n| (n.app_id, n.clone()))
+            .map(|g| g.appid)
             .collect();
         let target_set: HashSet<i32> = game_target_store::get_game_targets().expect("Failed to load targets")
             .iter()
@@ -58,8 +64,9 @@ impl GameListDisplay {
             .collect();
 
         let owned_games_vec: Vec<&Game> = OWNED_GAMES.values().collect();
-        let mut list: Vec<(&&Game, i8)> = owned_games_vec
+        let mut list: Vec<(&&Game, Option<&GameProgress>)> = owned_games_vec
             .par_iter()
+            .filter(|g| tagged_set.contains(&g.appid))
             .filter(|g| {
                 if let Some(search) = &title_search {
                     g.name.to_uppercase().contains(search.to_uppercase().as_str())
@@ -74,27 +81,27 @@ impl GameListDisplay {
                         target_set.contains(&g.appid)
                     }
                     GameListFilter::InProgress => {
-                        completed_games_cache.get(&g.appid).map(|c| c.complete).unwrap_or(0) < 100 || target_set.contains(&g.appid)
+                        !game_progress.get(&g.appid).map(|p| p.complete).unwrap_or(false) || target_set.contains(&g.appid)
                     },
                     GameListFilter::Completed => {
-                        completed_games_cache.get(&g.appid).map(|c| c.complete).unwrap_or(0) == 100 && !target_set.contains(&g.appid)
+                        game_progress.get(&g.appid).map(|p| p.complete).unwrap_or(false) && !target_set.contains(&g.appid)
                     },
                     GameListFilter::Perfected => {
-                        completed_games_cache.get(&g.appid).map(|c| c.perfect).unwrap_or(false) && !target_set.contains(&g.appid)
+                        game_progress.get(&g.appid).map(|p| p.perfect).unwrap_or(false) && !target_set.contains(&g.appid)
                     }
                 }
             })
             .filter(|g| {
                 if has_achievements {
-                    completed_games_cache.get(&g.appid).map(|c| c.has_achievements).unwrap_or(false)
+                    game_progress.get(&g.appid).map(|p| p.completion.has_achievements).unwrap_or(false)
                 }
                 else {
                     true
                 }
             })
-            .map(|g| (g, completed_games_cache.get(&g.appid).map(|c| c.complete).unwrap_or(0))) // Game, Progress
+            .map(|g| (g, game_progress.get(&g.appid))) // Game, Progress
             .collect();
-        list.sort_by_key(|a| Reverse(a.1));
+        list.sort_by_key(|a| Reverse(a.1.map(|p| p.progress).unwrap_or(0)));
 
         GameListResult {
             filter,
@@ -104,7 +111,10 @@ impl GameListDisplay {
                 .map(|g| {
                     GameListDisplay{
                         game_name: g.0.name.clone(),
-                        progress_display: g.1.to_string(),
+                        progress_display: g.1.map(|p| p.progress).unwrap_or(0).to_string(),
+                        achievements_display: g.1.map(|p| p.display()).unwrap_or("-".to_string()),
+                        score_display: game_scores.get(&g.0.appid).map(|s| s.points.to_string()).unwrap_or("-".to_string()),
+                        time_left_display: time_estimates.get(&g.0.appid).map(|e| e.display()).unwrap_or("-".to_string()),
                         id: g.0.appid,
                     }
                 })
@@ -124,11 +134,25 @@ impl App {
             ]
         };
 
-        let random_game = button("Random Game").on_press(Message::RandomGame);
+        let random_game = row![
+            button("Random Game").on_press(Message::RandomGame),
+            text_input("Under hours...", &self.random_under_hours)
+                .on_input(Message::RandomUnderHoursInput)
+                .width(120),
+        ].spacing(5);
+
+        let tag_filter = row![
+            pick_list(self.tags.clone(), self.selected_tag.clone(), |t| Message::TagFilterSelected(Some(t)))
+                .placeholder("All games"),
+            button("Clear").on_press(Message::TagFilterSelected(None)),
+        ].spacing(5);
 
         let achievement_filter = checkbox(self.games_have_achievements_filter)
             .label("Has Achievements")
             .on_toggle(Message::AchievementCheckboxToggled);
+        let formula_filter = checkbox(self.completion_policy.formula == CompletionFormula::ExclusionAdjusted)
+            .label("Ignore Excluded Achievements")
+            .on_toggle(Message::CompletionFormulaToggled);
         // Check if game list for selection ahs loaded
         let game_list = self.games.get(&(filter, self.games_have_achievements_filter));
         
@@ -154,6 +178,9 @@ impl App {
                 let columns = [
                     table::column(bold("Game Name"), |game: &GameListDisplay| button(game.game_name.as_str()).on_press(Message::GameView(game.id))),
                     table::column(bold("Progress"), |game: &GameListDisplay| text(game.progress_display.as_str())),
+                    table::column(bold("Achievements"), |game: &GameListDisplay| text(game.achievements_display.as_str())),
+                    table::column(bold("Score"), |game: &GameListDisplay| text(game.score_display.as_str())),
+                    table::column(bold("Time Left"), |game: &GameListDisplay| text(game.time_left_display.as_str())),
                 ];
 
                 column![
@@ -170,7 +197,8 @@ impl App {
         };
         column![
             center_x(filter_games).padding(5),
-            center_x(achievement_filter).padding(5),
+            center_x(row![achievement_filter, formula_filter].spacing(10)).padding(5),
+            center_x(tag_filter).padding(5),
             center_x(random_game).padding(5),
             center_x(game_count).padding(5),
             center_x(game_search).padding(5),
@@ -4,14 +4,27 @@ use crate::{Credentials, Message};
 
 use iced::font;
 use iced::widget::{
-    table, text, center_x, center_y, column, scrollable, image
+    table, text, center_x, center_y, column, row, scrollable, image, button
 };
 use iced::{Center, Left, Font, Element};
 use db::{
     achievement_store, 
 };
 use api::game_fetch;
+use chrono::{Local, NaiveDate};
 use std::collections::HashMap;
+use std::cmp::Reverse;
+
+// Goals due within this many days count as due soon
+const DUE_SOON_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GoalFilter {
+    #[default]
+    All,
+    Overdue,
+    DueSoon,
+}
 
 #[derive(Debug, Clone)]
 pub struct Goal {
@@ -20,8 +33,13 @@ pub struct Goal {
     pub display_name: String,
     pub description: String,
     // DATA
+    pub id: i32,
     pub app_id: i32,
     pub achievement_name: String,
+    pub priority: i32,
+    pub due_date: Option<NaiveDate>,
+    pub overdue: bool,
+    pub due_soon: bool,
 }
 
 impl Goal {
@@ -31,23 +49,50 @@ impl Goal {
             .map(|g| (g.appid, g))
             .collect::<HashMap<_, _>>();
 
-        let mut goals = achievement_store::get_achievements().expect("Failed to load achievements");
-        goals.sort_by(|a, b| i32::cmp(&a.app_id,&b.app_id));
+        let today = Local::now().date_naive();
+        // The store returns goals ordered by priority and due date
+        let goals = achievement_store::get_achievements().expect("Failed to load achievements");
         goals.iter().map(|g| Goal {
-                game_name: game_map.get(&g.app_id).unwrap().name.clone(),
+                game_name: game_map.get(&g.app_id).map(|game| game.name.clone()).unwrap_or(g.app_id.to_string()),
                 display_name: g.display_name.clone(),
                 description: g.description.clone().unwrap_or("-".to_string()),
+                id: g.id,
                 app_id: g.app_id,
                 achievement_name: g.achievement_name.clone(),
+                priority: g.priority,
+                due_date: g.due_date,
+                overdue: g.is_overdue(today),
+                due_soon: g.is_due_within(today, DUE_SOON_DAYS),
             })
             .collect()
     }
+
+    /// Keep the same order as the store after a change
+    pub fn sort(goals: &mut [Goal]) {
+        goals.sort_by_key(|g| (Reverse(g.priority), g.due_date.is_none(), g.due_date, g.app_id));
+    }
 }
 
 impl App {
     pub fn goal_view(&self) -> Element<'_, Message> {
+        let filter_goals = {
+            row![
+                button("All").on_press(Message::GoalFilterSelected(GoalFilter::All)),
+                button("Overdue").on_press(Message::GoalFilterSelected(GoalFilter::Overdue)),
+                button("Due soon").on_press(Message::GoalFilterSelected(GoalFilter::DueSoon)),
+            ]
+        };
+
         let main_view = if let Some(goals) = &self.goals {
             {
+                let filtered_goals = goals.iter()
+                    .filter(|g| {
+                        match self.goal_filter {
+                            GoalFilter::All => true,
+                            GoalFilter::Overdue => g.overdue,
+                            GoalFilter::DueSoon => g.due_soon,
+                        }
+                    });
                 let bold = |header| {
                     text(header).font(Font {
                         weight: font::Weight::Bold,
@@ -74,9 +119,36 @@ impl App {
                     table::column(bold("Description"), |goal: &Goal| text(&goal.description))
                         .align_x(Left)
                         .align_y(Center),
+                    table::column(bold("Priority"), |goal: &Goal| {
+                            row![
+                                button("-").on_press(Message::ChangeGoalPriority(goal.id, goal.priority - 1)),
+                                text(goal.priority.to_string()),
+                                button("+").on_press(Message::ChangeGoalPriority(goal.id, goal.priority + 1)),
+                            ].spacing(5).align_y(Center)
+                        })
+                        .align_x(Left)
+                        .align_y(Center),
+                    table::column(bold("Due"), |goal: &Goal| {
+                            text(goal.due_date.map(|d| d.to_string()).unwrap_or("-".to_string())).style({
+                                if goal.overdue {
+                                    text::danger
+                                }
+                                else if goal.due_soon {
+                                    text::warning
+                                }
+                                else {
+                                    text::default
+                                }
+                            })
+                        })
+                        .align_x(Left)
+                        .align_y(Center),
+                    table::column(bold("Skip"), |goal: &Goal| button("Skip").on_press(Message::SkipGoal(goal.id)))
+                        .align_x(Left)
+                        .align_y(Center),
                 ];
 
-                column![table(columns, goals)
+                column![table(columns, filtered_goals)
                     .padding_x(10)
                     .padding_y(5)
                     .separator_x(1)
@@ -90,6 +162,7 @@ impl App {
         };
 
         column![
+            center_x(filter_goals).padding(5),
             center_y(scrollable(center_x(main_view)).spacing(10)).padding(10),
         ].into()
     }
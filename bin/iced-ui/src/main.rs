@@ -2,31 +2,48 @@ mod games_list_view;
 mod goals_view;
 mod game_view;
 mod trophy_case_view;
+mod exclusions_view;
+mod search_view;
+mod daily_view;
+mod bingo_view;
 
 use iced::widget::{
-    center_x, column, row, button, image::Handle, text, 
+    center_x, column, row, button, image::Handle, text, text_input,
 };
-use iced::{Element, Theme, Task};
+use iced::{Element, Subscription, Theme, Task};
 use games_list_view::{
     GameListDisplay, 
     GameListFilter, 
     GameListResult
 };
-use goals_view::Goal;
+use goals_view::{Goal, GoalFilter};
 use api::game_fetch::{self, Game};
 use simple_error::SimpleError;
 use std::env;
 use std::collections::HashMap;
 use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
 use db::{
     steam_id_store,
     game_target_store,
+    achievement_store,
     excluded_achievement_store,
+    excluded_achievement_store::ExclusionReason,
+    collection_store,
+    note_store,
+    schema_change_store,
+    schema_change_store::SchemaChange,
+    game_completion_cache::CompletionFormula,
 };
-use goals_lib::goals;
+use goals_lib::{completion::CompletionPolicy, goals, roll, roll::Roller, settings, time_estimate};
 use game_view::{GameDisplay, GameGoalDisplay};
 use api::achievement_fetch::GameAchievement;
 use trophy_case_view::TrophyCaseFilter;
+use exclusions_view::Exclusion;
+use search_view::SearchResult;
+use daily_view::DailyResult;
+use bingo_view::BingoDisplay;
 
 // We only need to load this once, do it statically so it can be shared between all threads
 pub static OWNED_GAMES: LazyLock<HashMap<i32, Game>> = LazyLock::new(|| {
@@ -40,11 +57,17 @@ pub static OWNED_GAMES: LazyLock<HashMap<i32, Game>> = LazyLock::new(|| {
     }
 );
 
+// Progress of the running completion refresh, written by the sync task and read on each tick
+static REFRESH_DONE: AtomicUsize = AtomicUsize::new(0);
+static REFRESH_TOTAL: AtomicUsize = AtomicUsize::new(0);
+static REFRESH_CANCEL: AtomicBool = AtomicBool::new(false);
+
 pub fn main() -> iced::Result {
     // Do this call to instantiate the owned games list before the program starts
     OWNED_GAMES.len();
     color_eyre::install().expect("Failed to install color eyre");
     iced::application(App::new, App::update, App::view)
+        .subscription(App::subscription)
         .theme(Theme::CatppuccinMocha)
         .run()
 }
@@ -58,18 +81,52 @@ enum Message {
     GoalsView,
     GoalsLoaded(Vec<Goal>),
     AchievementCheckboxToggled(bool),
+    CompletionFormulaToggled(bool),
     GamesLoaded(GameListResult),
     GenerateRandomAchievement(i32), // app_id
-    RandomAchievementGenerated(Result<(Game, Option<GameAchievement>), SimpleError>), 
+    RandomAchievementGenerated(Result<(Game, Option<GameAchievement>, i64), SimpleError>), // game, achievement, seed
     SetAsGameTarget(i32), // app_id
     SetGameAsComplete(i32), // app_id
     RandomGame,
+    RandomUnderHoursInput(String),
     ExcludeAchievement(i32, String), // app_id, achievement_name
+    ExclusionsView,
+    ExclusionsLoaded(Vec<Exclusion>),
+    UpdateExclusionReason(i32, i32, Option<ExclusionReason>), // app_id, exclusion_id, reason, None clears it
+    RemoveExclusion(i32, i32), // app_id, exclusion_id
     TrophyCaseView(TrophyCaseFilter),
     TrophiesLoaded(Vec<i32>), // app_id's
     GameCoversLoaded(HashMap<i32, Handle>), // app_id -> Game Cover
-    CachesSynced(Result<(), SimpleError>),
+    CachesSynced(Result<goals::RefreshOutcome, SimpleError>),
+    GameCompletionRefreshed(Result<(), SimpleError>),
+    RefreshProgressTick,
+    DismissSchemaChange(i32), // schema change id
+    CancelRefresh,
+    ResumeRefresh,
     GameListSearch(String),
+    TagFilterSelected(Option<String>),
+    TagInput(String),
+    TagGame(i32), // app_id
+    UntagGame(i32, String), // app_id, tag
+    GoalFilterSelected(GoalFilter),
+    ChangeGoalPriority(i32, i32), // goal id, priority
+    SkipGoal(i32), // goal id
+    AchievementSearchInput(String),
+    AchievementSearchSubmitted,
+    SearchUnachievedToggled(bool),
+    SearchResultsLoaded(Result<Vec<SearchResult>, String>),
+    DailyView,
+    DailyLoaded(DailyResult),
+    BingoView,
+    BingoLoaded(BingoDisplay),
+    BingoIconsLoaded(HashMap<(i32, String, bool), Handle>), // app_id, achievement_name, completed -> Image
+    BingoSizeSelected(usize),
+    BingoBalanceToggled(bool),
+    GenerateBingo,
+    GameNoteChanged(i32, String), // app_id, note
+    GameNoteSubmitted(i32), // app_id
+    GoalNoteChanged(i32, String, String), // app_id, achievement_name, note
+    GoalNoteSubmitted(i32, String), // app_id, achievement_name
 }
 
 #[derive(Debug, Clone, Default)]
@@ -80,6 +137,10 @@ enum View {
     Games(GameListFilter),
     Game(i32), // app_id
     TrophyCase,
+    Exclusions,
+    Search,
+    Daily,
+    Bingo,
 }
 
 #[derive(Debug, Clone)]
@@ -94,39 +155,83 @@ struct App {
     // DISPLAY
     games: HashMap<(GameListFilter, bool), Vec<GameListDisplay>>, // filter, has_achievement -> game_list
     games_have_achievements_filter: bool,
+    completion_policy: CompletionPolicy,
     game_list_search: String,
+    random_under_hours: String, // Random Game only picks games estimated to take at most this many hours when it is set
     goals: Option<Vec<Goal>>,
+    goal_filter: GoalFilter,
     game_views: HashMap<i32, GameDisplay>,
     goal_icons: HashMap<(i32, String), Handle>, // app_id, achievement_name -> image
     trophies: Option<Vec<i32>>,
     game_covers: HashMap<i32, Handle>, // app_id -> image
+    exclusions: Option<Vec<Exclusion>>,
+    tags: Vec<String>,
+    selected_tag: Option<String>,
+    tag_input: String,
+    trophy_case_filter: TrophyCaseFilter,
+    schema_changes: Vec<SchemaChange>,
+    achievement_search: String,
+    search_unachieved: bool,
+    search_results: Option<Result<Vec<SearchResult>, String>>,
+    daily: Option<DailyResult>,
+    bingo: Option<BingoDisplay>,
+    bingo_icons: HashMap<(i32, String, bool), Handle>, // app_id, achievement_name, completed -> image
+    bingo_size: usize,
+    bingo_balance_rarity: bool,
+    syncing: bool,
+    refresh_progress: (usize, usize), // done, total
+    refresh_stopped: Option<String>, // Why the last refresh stopped before finishing
+    game_refresh_error: Option<String>, // Why the last refresh of a single game failed
     // DATA
     credentials: Credentials,
 }
 
 impl App {
-    fn new() -> Self {
+    fn new() -> (Self, Task<Message>) {
         let credentials = load_credentials();
-        tokio::runtime::Runtime::new().expect("Unable to create a runtime").block_on(sync_caches(credentials.clone())).expect("Failed to sync caches");
-        Self {
+        let mut app = Self {
             view: View::default(),
             games: HashMap::new(),
             games_have_achievements_filter: true,
+            completion_policy: settings::get_completion_policy(),
             game_list_search: "".to_string(),
+            random_under_hours: "".to_string(),
             goals: None,
+            goal_filter: GoalFilter::default(),
             game_views: HashMap::new(),
             goal_icons: HashMap::new(),
             game_covers: HashMap::new(),
             trophies: None,
+            exclusions: None,
+            tags: load_tags(),
+            selected_tag: None,
+            tag_input: "".to_string(),
+            trophy_case_filter: TrophyCaseFilter::default(),
+            schema_changes: load_schema_changes(),
+            achievement_search: "".to_string(),
+            search_unachieved: false,
+            search_results: None,
+            daily: None,
+            bingo: None,
+            bingo_icons: HashMap::new(),
+            bingo_size: 5,
+            bingo_balance_rarity: false,
+            syncing: false,
+            refresh_progress: (0, 0),
+            refresh_stopped: None,
+            game_refresh_error: None,
             credentials,
-        }
+        };
+        // The first refresh runs in the background like any other, so it shows progress and can be cancelled or resumed
+        let sync = app.sync_task();
+        (app, sync)
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::GamesView(filter) => {
                 self.view = View::Games(filter.clone());
-                Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone())), Message::GamesLoaded)
+                Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone()), self.completion_policy, self.selected_tag.clone()), Message::GamesLoaded)
             },
             Message::GamesLoaded(list_result) => {
                 self.games.insert((list_result.filter, list_result.has_achievements), list_result.list);
@@ -176,7 +281,19 @@ impl App {
                 self.games_have_achievements_filter = is_checked;
                 match &self.view {
                     View::Games(filter) => {
-                        Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone())), Message::GamesLoaded)
+                        Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone()), self.completion_policy, self.selected_tag.clone()), Message::GamesLoaded)
+                    },
+                    _ => Task::none()
+                }
+            },
+            Message::CompletionFormulaToggled(is_checked) => {
+                self.completion_policy.formula = if is_checked { CompletionFormula::ExclusionAdjusted } else { CompletionFormula::Raw };
+                settings::set_completion_policy(&self.completion_policy).expect("Failed to save the completion policy");
+                // Every loaded list was calculated with the old formula
+                self.games.clear();
+                match &self.view {
+                    View::Games(filter) => {
+                        Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone()), self.completion_policy, self.selected_tag.clone()), Message::GamesLoaded)
                     },
                     _ => Task::none()
                 }
@@ -188,7 +305,7 @@ impl App {
                         Task::perform(Goal::list(self.credentials.clone()), Message::GoalsLoaded), 
                         Task::perform(game_view::load_game_display(self.credentials.clone(), r.0.appid, r.0.name.clone()), Message::GameLoaded)
                     ];
-                    self.handle_generated_random_achievement(r.0, r.1);
+                    self.handle_generated_random_achievement(r.0, r.1, r.2);
                     Task::batch(tasks)
                 }
                 else {
@@ -200,31 +317,71 @@ impl App {
                 if let Some(view) = self.game_views.get_mut(&app_id) {
                     view.target = true;
                 }
-                Task::perform(sync_caches(self.credentials.clone()), Message::CachesSynced)
+                self.sync_task()
             },
             Message::SetGameAsComplete(app_id) => {
                 game_target_store::save_game_target(&app_id, &true).expect("Failed to save target");
                 if let Some(view) = self.game_views.get_mut(&app_id) {
                     view.complete = true;
                 }
-                Task::perform(sync_caches(self.credentials.clone()), Message::CachesSynced)
+                self.sync_task()
             },
             Message::RandomGame => {
-                let random_game_id = OWNED_GAMES.values().nth(rand::random_range(..OWNED_GAMES.values().len())).unwrap().appid;
+                let mut games = goals::filter_to_collection(OWNED_GAMES.values().cloned().collect(), &self.selected_tag);
+                if let Ok(hours) = self.random_under_hours.trim().parse::<f64>() {
+                    games = time_estimate::games_under(games, hours, self.completion_policy);
+                }
+                let mut cooldown = roll::Cooldown::load();
+                let Some(random_game) = roll::take_random_game(&mut games, &cooldown, Roller::new(None).rng()) else {
+                    return Task::none();
+                };
+                cooldown.record(&random_game);
+                let random_game_id = random_game.appid;
                 self.view = View::Game(random_game_id).clone();
                 Task::perform(game_view::load_game_display(self.credentials.clone(), random_game_id, OWNED_GAMES.get(&random_game_id).expect("Does not exist").name.clone()), Message::GameLoaded)
             },
+            Message::RandomUnderHoursInput(hours) => {
+                self.random_under_hours = hours;
+                Task::none()
+            },
             Message::ExcludeAchievement(app_id, achievement_name) => {
-                excluded_achievement_store::save_excluded_achievement(&achievement_name, &app_id).expect("Failed to exclude achievement");
+                excluded_achievement_store::save_excluded_achievement(&achievement_name, &app_id, &None).expect("Failed to exclude achievement");
                 let tasks = vec![
                     Task::perform(game_view::load_game_display(self.credentials.clone(), app_id, OWNED_GAMES.get(&app_id).expect("Does not exist").name.clone()), Message::GameLoaded),
-                    Task::perform(sync_caches(self.credentials.clone()), Message::CachesSynced)
+                    Task::perform(Exclusion::list(), Message::ExclusionsLoaded),
+                    Task::perform(refresh_game_completion(self.credentials.clone(), app_id), Message::GameCompletionRefreshed)
+                ];
+                Task::batch(tasks)
+            },
+            Message::ExclusionsView => {
+                self.view = View::Exclusions;
+                Task::perform(Exclusion::list(), Message::ExclusionsLoaded)
+            },
+            Message::ExclusionsLoaded(exclusions) => {
+                self.exclusions = Some(exclusions);
+                Task::none()
+            },
+            Message::UpdateExclusionReason(app_id, exclusion_id, reason) => {
+                excluded_achievement_store::update_excluded_achievement_reason(&exclusion_id, &reason).expect("Failed to update exclusion");
+                let tasks = vec![
+                    Task::perform(game_view::load_game_display(self.credentials.clone(), app_id, OWNED_GAMES.get(&app_id).expect("Does not exist").name.clone()), Message::GameLoaded),
+                    Task::perform(Exclusion::list(), Message::ExclusionsLoaded),
+                ];
+                Task::batch(tasks)
+            },
+            Message::RemoveExclusion(app_id, exclusion_id) => {
+                excluded_achievement_store::delete_excluded_achievement(&exclusion_id).expect("Failed to remove exclusion");
+                let tasks = vec![
+                    Task::perform(game_view::load_game_display(self.credentials.clone(), app_id, OWNED_GAMES.get(&app_id).expect("Does not exist").name.clone()), Message::GameLoaded),
+                    Task::perform(Exclusion::list(), Message::ExclusionsLoaded),
+                    Task::perform(refresh_game_completion(self.credentials.clone(), app_id), Message::GameCompletionRefreshed)
                 ];
                 Task::batch(tasks)
             },
             Message::TrophyCaseView(filter) => {
                 self.view = View::TrophyCase;
-                Task::perform(trophy_case_view::load_trophies(filter), Message::TrophiesLoaded)
+                self.trophy_case_filter = filter;
+                Task::perform(trophy_case_view::load_trophies(filter, self.completion_policy, self.selected_tag.clone()), Message::TrophiesLoaded)
             },
             Message::TrophiesLoaded(trophies) => {
                 let filtered_covers: Vec<i32> = trophies.iter()
@@ -245,36 +402,262 @@ impl App {
                 Task::none()
             },
             Message::CachesSynced(result) => {
-                if result.is_err() {
-                    panic!("Caches failed to sync")
-                }
-                let mut tasks: Vec<Task<Message>> = vec![];
-                for k in self.games.keys() {
-                    tasks.push(Task::perform(GameListDisplay::list(k.1, k.0.clone(), Some(self.game_list_search.clone())), Message::GamesLoaded));
-                }
-                self.trophies = None;
-                Task::batch(tasks)
+                self.syncing = false;
+                self.refresh_progress = (REFRESH_DONE.load(Ordering::Relaxed), REFRESH_TOTAL.load(Ordering::Relaxed));
+                self.refresh_stopped = match result {
+                    Err(e) => Some(e.to_string()),
+                    Ok(goals::RefreshOutcome::Cancelled) => Some("Cancelled".to_string()),
+                    Ok(goals::RefreshOutcome::Completed) => None,
+                };
+                self.reload_completion()
             },
+            Message::GameCompletionRefreshed(result) => {
+                self.game_refresh_error = result.err().map(|e| e.to_string());
+                self.reload_completion()
+            },
+            Message::RefreshProgressTick => {
+                self.refresh_progress = (REFRESH_DONE.load(Ordering::Relaxed), REFRESH_TOTAL.load(Ordering::Relaxed));
+                Task::none()
+            },
+            Message::DismissSchemaChange(id) => {
+                schema_change_store::delete_schema_change(&id).expect("Failed to dismiss schema change");
+                self.schema_changes = load_schema_changes();
+                Task::none()
+            },
+            Message::CancelRefresh => {
+                REFRESH_CANCEL.store(true, Ordering::Relaxed);
+                Task::none()
+            },
+            Message::ResumeRefresh => self.sync_task(),
             Message::GameListSearch(search) => {
                 self.game_list_search = search.clone();
                 
                 match &self.view {
                     View::Games(filter) => {
-                        Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone())), Message::GamesLoaded)
+                        Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone()), self.completion_policy, self.selected_tag.clone()), Message::GamesLoaded)
+                    },
+                    _ => Task::none()
+                }
+            },
+            Message::TagFilterSelected(tag) => {
+                self.selected_tag = tag;
+                // Every loaded list was filtered with the old tag
+                self.games.clear();
+                self.trophies = None;
+                match &self.view {
+                    View::Games(filter) => {
+                        Task::perform(GameListDisplay::list(self.games_have_achievements_filter, filter.clone(), Some(self.game_list_search.clone()), self.completion_policy, self.selected_tag.clone()), Message::GamesLoaded)
+                    },
+                    View::TrophyCase => {
+                        Task::perform(trophy_case_view::load_trophies(self.trophy_case_filter, self.completion_policy, self.selected_tag.clone()), Message::TrophiesLoaded)
                     },
                     _ => Task::none()
                 }
             },
+            Message::TagInput(tag) => {
+                self.tag_input = tag;
+                Task::none()
+            },
+            Message::TagGame(app_id) => {
+                let tag = self.tag_input.trim().to_string();
+                if tag.is_empty() {
+                    return Task::none();
+                }
+                let collection_id = collection_store::save_collection(&tag).expect("Failed to save tag");
+                collection_store::add_game_to_collection(&collection_id, &app_id).expect("Failed to tag game");
+                self.tag_input = "".to_string();
+                self.tags = load_tags();
+                if let Some(view) = self.game_views.get_mut(&app_id) {
+                    if !view.tags.contains(&tag) {
+                        view.tags.push(tag);
+                        view.tags.sort();
+                    }
+                }
+                Task::none()
+            },
+            Message::UntagGame(app_id, tag) => {
+                if let Some(collection) = collection_store::get_collection(&tag).expect("Failed to load tag") {
+                    collection_store::remove_game_from_collection(&collection.id, &app_id).expect("Failed to untag game");
+                }
+                if let Some(view) = self.game_views.get_mut(&app_id) {
+                    view.tags.retain(|t| *t != tag);
+                }
+                Task::none()
+            },
+            Message::GoalFilterSelected(filter) => {
+                self.goal_filter = filter;
+                Task::none()
+            },
+            Message::ChangeGoalPriority(id, priority) => {
+                achievement_store::update_priority(&id, &priority).expect("Failed to save the priority");
+                if let Some(goals) = self.goals.as_mut() {
+                    if let Some(goal) = goals.iter_mut().find(|g| g.id == id) {
+                        goal.priority = priority;
+                    }
+                    Goal::sort(goals);
+                }
+                Task::none()
+            },
+            Message::SkipGoal(id) => {
+                let (goal, _) = goals::skip_goal(&id).expect("Failed to skip the goal");
+                if let Some(goals) = self.goals.as_mut() {
+                    goals.retain(|g| g.id != id);
+                }
+                let game_name = OWNED_GAMES.get(&goal.app_id).map(|g| g.name.clone()).unwrap_or(goal.app_id.to_string());
+                Task::perform(game_view::load_game_display(self.credentials.clone(), goal.app_id, game_name), Message::GameLoaded)
+            },
+            Message::AchievementSearchInput(search) => {
+                self.achievement_search = search;
+                Task::none()
+            },
+            Message::AchievementSearchSubmitted => {
+                self.view = View::Search;
+                self.search_results = None;
+                Task::perform(SearchResult::list(self.credentials.clone(), self.achievement_search.clone(), self.search_unachieved), Message::SearchResultsLoaded)
+            },
+            Message::SearchUnachievedToggled(is_checked) => {
+                self.search_unachieved = is_checked;
+                self.search_results = None;
+                Task::perform(SearchResult::list(self.credentials.clone(), self.achievement_search.clone(), self.search_unachieved), Message::SearchResultsLoaded)
+            },
+            Message::DailyView => {
+                self.view = View::Daily;
+                self.daily = None;
+                Task::perform(DailyResult::load(self.credentials.clone()), Message::DailyLoaded)
+            },
+            Message::BingoView => {
+                self.view = View::Bingo;
+                self.bingo = None;
+                Task::perform(BingoDisplay::load(self.credentials.clone()), Message::BingoLoaded)
+            },
+            Message::BingoLoaded(display) => {
+                let task = match &display.card {
+                    Some(card) if card.items.iter().any(|i| !self.bingo_icons.contains_key(&(i.app_id, i.achievement_name.clone(), i.completed))) => {
+                        Task::perform(bingo_view::load_bingo_icons(card.clone()), Message::BingoIconsLoaded)
+                    },
+                    _ => Task::none(),
+                };
+                self.bingo = Some(display);
+                task
+            },
+            Message::BingoIconsLoaded(icons) => {
+                self.bingo_icons.extend(icons);
+                Task::none()
+            },
+            Message::BingoSizeSelected(size) => {
+                self.bingo_size = size;
+                Task::none()
+            },
+            Message::BingoBalanceToggled(is_checked) => {
+                self.bingo_balance_rarity = is_checked;
+                Task::none()
+            },
+            Message::GenerateBingo => {
+                self.bingo = None;
+                Task::perform(BingoDisplay::generate(self.credentials.clone(), self.bingo_size, self.bingo_balance_rarity, self.selected_tag.clone()), Message::BingoLoaded)
+            },
+            Message::DailyLoaded(daily) => {
+                self.daily = Some(daily);
+                Task::none()
+            },
+            Message::SearchResultsLoaded(results) => {
+                self.search_results = Some(results);
+                Task::none()
+            },
+            // Notes are only saved when submitted rather than on every key
+            Message::GameNoteChanged(app_id, note) => {
+                if let Some(view) = self.game_views.get_mut(&app_id) {
+                    view.note = note;
+                }
+                Task::none()
+            },
+            Message::GameNoteSubmitted(app_id) => {
+                if let Some(view) = self.game_views.get(&app_id) {
+                    note_store::save_note(&app_id, &None, &view.note).expect("Failed to save note");
+                }
+                Task::none()
+            },
+            Message::GoalNoteChanged(app_id, achievement_name, note) => {
+                if let Some(view) = self.game_views.get_mut(&app_id) {
+                    if let Some(goal) = view.goals.iter_mut().find(|g| g.achievement_name == achievement_name) {
+                        goal.note = note;
+                    }
+                }
+                Task::none()
+            },
+            Message::GoalNoteSubmitted(app_id, achievement_name) => {
+                if let Some(goal) = self.game_views.get(&app_id).and_then(|v| v.goals.iter().find(|g| g.achievement_name == achievement_name)) {
+                    note_store::save_note(&app_id, &Some(achievement_name.clone()), &goal.note).expect("Failed to save note");
+                }
+                Task::none()
+            },
+        }
+    }
+
+    /// Start a background sync, the refresh resumes from its checkpoint if the last one stopped
+    fn sync_task(&mut self) -> Task<Message> {
+        if self.syncing {
+            return Task::none();
+        }
+        self.syncing = true;
+        self.refresh_stopped = None;
+        REFRESH_CANCEL.store(false, Ordering::Relaxed);
+        Task::perform(sync_caches(self.credentials.clone()), Message::CachesSynced)
+    }
+
+    /// Reload everything showing completion once a refresh has changed it
+    fn reload_completion(&mut self) -> Task<Message> {
+        let mut tasks: Vec<Task<Message>> = vec![];
+        for k in self.games.keys() {
+            tasks.push(Task::perform(GameListDisplay::list(k.1, k.0.clone(), Some(self.game_list_search.clone()), self.completion_policy, self.selected_tag.clone()), Message::GamesLoaded));
+        }
+        self.trophies = None;
+        self.schema_changes = load_schema_changes();
+        Task::batch(tasks)
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        if self.syncing {
+            iced::time::every(Duration::from_millis(500)).map(|_| Message::RefreshProgressTick)
+        }
+        else {
+            Subscription::none()
         }
     }
 
     fn view(&self) -> Element<'_, Message> {
+        let refresh_status: Element<'_, Message> = if self.syncing {
+            row![
+                text(format!("Refreshing {done}/{total}", done = self.refresh_progress.0, total = self.refresh_progress.1)),
+                button("Cancel").on_press(Message::CancelRefresh),
+            ].spacing(5).into()
+        }
+        else if let Some(reason) = &self.refresh_stopped {
+            row![
+                text(format!("Refresh stopped after {done}/{total}: {reason}", done = self.refresh_progress.0, total = self.refresh_progress.1)),
+                button("Resume").on_press(Message::ResumeRefresh),
+            ].spacing(5).into()
+        }
+        else if let Some(error) = &self.game_refresh_error {
+            text(format!("Failed to refresh the game: {error}")).into()
+        }
+        else {
+            row![].into()
+        };
         let view_selector = {
             row![
                 button("Games").on_press(Message::GamesView(GameListFilter::default())),
                 button("Goals").on_press(Message::GoalsView),
                 button("Trophy Case").on_press(Message::TrophyCaseView(TrophyCaseFilter::default())),
-            ]
+                button("Exclusions").on_press(Message::ExclusionsView),
+                button("Daily").on_press(Message::DailyView),
+                button("Bingo").on_press(Message::BingoView),
+                text_input("Search achievements...", &self.achievement_search)
+                    .on_input(Message::AchievementSearchInput)
+                    .on_submit(Message::AchievementSearchSubmitted)
+                    .width(250),
+                refresh_status,
+            ].spacing(5)
         };
 
         let main_view: Element<'_, Message> = match &self.view {
@@ -283,6 +666,10 @@ impl App {
             View::Games(filter) => self.game_list_view(filter.clone()),
             View::Game(_) => self.game_view(),
             View::TrophyCase => self.trophy_case_view(),
+            View::Exclusions => self.exclusions_view(),
+            View::Search => self.search_view(),
+            View::Daily => self.daily_view(),
+            View::Bingo => self.bingo_view(),
         };
 
         column![
@@ -300,9 +687,35 @@ fn load_credentials() -> Credentials {
     }
 }
 
-async fn sync_caches(credentials: Credentials) -> Result<(), SimpleError> {
+fn load_schema_changes() -> Vec<SchemaChange> {
+    schema_change_store::get_schema_changes().expect("Failed to load schema changes")
+}
+
+fn load_tags() -> Vec<String> {
+    collection_store::get_collections().expect("Failed to load tags")
+        .into_iter()
+        .map(|c| c.name)
+        .collect()
+}
+
+async fn sync_caches(credentials: Credentials) -> Result<goals::RefreshOutcome, SimpleError> {
     goals::get_and_sync_completed_achievements(&credentials.key, &credentials.steam_id).await;
-    let owned_games = OWNED_GAMES.values().cloned().collect();
-    goals::refresh_game_completion_cache(&credentials.key, &credentials.steam_id, &owned_games).await;
-    Ok(())
+    let owned_games: Vec<Game> = OWNED_GAMES.values().cloned().collect();
+    REFRESH_DONE.store(0, Ordering::Relaxed);
+    REFRESH_TOTAL.store(0, Ordering::Relaxed);
+    goals::refresh_game_completion_cache_with_progress(&credentials.key, &credentials.steam_id, &owned_games, |p| {
+        REFRESH_DONE.store(p.done, Ordering::Relaxed);
+        REFRESH_TOTAL.store(p.total, Ordering::Relaxed);
+    }, &REFRESH_CANCEL).await
+        .map_err(SimpleError::new)
+}
+
+async fn refresh_game_completion(credentials: Credentials, app_id: i32) -> Result<(), SimpleError> {
+    if let Some(game) = OWNED_GAMES.get(&app_id) {
+        goals::refresh_game_completion_for_app(&credentials.key, &credentials.steam_id, game).await
+            .map_err(SimpleError::new)
+    }
+    else {
+        Err(SimpleError::new("No game with that app_id"))
+    }
 }
@@ -0,0 +1,101 @@
+use super::App;
+
+use crate::{Credentials, Message, OWNED_GAMES};
+
+use iced::font;
+use iced::widget::{
+    table, text, center_x, center_y, column, scrollable, button, checkbox
+};
+use iced::{Center, Left, Font, Element};
+use goals_lib::{estimate, search};
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    // DISPLAY
+    pub game_name: String,
+    pub display_name: String,
+    pub description: String,
+    pub goal: String,
+    // DATA
+    pub app_id: i32,
+}
+
+impl SearchResult {
+    /// An unachieved search makes a request per matching game, so it is checked against the quota first
+    pub async fn list(credentials: Credentials, query: String, unachieved_only: bool) -> Result<Vec<Self>, String> {
+        estimate::RequestEstimate::new(estimate::search(&query, unachieved_only)).check()?;
+        Ok(search::search(&credentials.key, &credentials.steam_id, &query, unachieved_only).await?
+            .into_iter()
+            .map(|r| SearchResult {
+                game_name: OWNED_GAMES.get(&r.app_id).map(|g| g.name.clone()).unwrap_or(r.app_id.to_string()),
+                display_name: r.display_name,
+                description: r.description.unwrap_or("-".to_string()),
+                goal: if r.is_goal { "Goal".to_string() } else { "".to_string() },
+                app_id: r.app_id,
+            })
+            .collect())
+    }
+}
+
+impl App {
+    pub fn search_view(&self) -> Element<'_, Message> {
+        let unachieved_filter = checkbox(self.search_unachieved)
+            .label("Unachieved Only")
+            .on_toggle(Message::SearchUnachievedToggled);
+
+        let main_view = if let Some(Err(e)) = &self.search_results {
+            column![text(e)]
+        }
+        else if let Some(Ok(results)) = &self.search_results {
+            let bold = |header| {
+                text(header).font(Font {
+                    weight: font::Weight::Bold,
+                    ..Font::DEFAULT
+                })
+            };
+            let columns = [
+                table::column(bold("Game Name"), |result: &SearchResult| {
+                        // Schemas can be cached for games that are no longer owned
+                        if OWNED_GAMES.contains_key(&result.app_id) {
+                            button(result.game_name.as_str()).on_press(Message::GameView(result.app_id))
+                        }
+                        else {
+                            button(result.game_name.as_str())
+                        }
+                    })
+                    .align_x(Left)
+                    .align_y(Center),
+                table::column(bold("Achievement Name"), |result: &SearchResult| text(&result.display_name))
+                    .align_x(Left)
+                    .align_y(Center),
+                table::column(bold("Description"), |result: &SearchResult| text(&result.description))
+                    .align_x(Left)
+                    .align_y(Center),
+                table::column(bold(""), |result: &SearchResult| text(&result.goal).style(text::warning))
+                    .align_x(Left)
+                    .align_y(Center),
+            ];
+
+            if results.is_empty() {
+                column![text("No achievements found, open a game to add it to the search")]
+            }
+            else {
+                column![table(columns, results)
+                    .padding_x(10)
+                    .padding_y(5)
+                    .separator_x(1)
+                    .separator_y(1)]
+            }
+        }
+        else {
+            column![
+                text("Searching...")
+            ]
+        };
+
+        column![
+            center_x(unachieved_filter).padding(5),
+            center_y(scrollable(center_x(main_view)).spacing(10)).padding(10),
+        ].into()
+    }
+}
@@ -5,12 +5,10 @@ use crate::{Message, OWNED_GAMES};
 use api::game_cover_fetch;
 use iced::{Element};
 use iced::widget::{
-    column, row, text, image, image::Handle, grid, scrollable, center_x, button
-};
-use db::{
-    game_completion_cache,
-    game_target_store,
+    column, row, text, image, image::Handle, grid, scrollable, center_x, button, pick_list
 };
+use db::game_target_store;
+use goals_lib::{completion, completion::CompletionPolicy, goals};
 use std::collections::{HashMap, HashSet};
 use rayon::prelude::*;
 
@@ -20,7 +18,10 @@ impl App {
             row![
                 button("Completed").on_press(Message::TrophyCaseView(TrophyCaseFilter::Completed)),
                 button("Perfected").on_press(Message::TrophyCaseView(TrophyCaseFilter::Perfected)),
-            ]
+                pick_list(self.tags.clone(), self.selected_tag.clone(), |t| Message::TagFilterSelected(Some(t)))
+                    .placeholder("All games"),
+                button("Clear").on_press(Message::TagFilterSelected(None)),
+            ].spacing(5)
         };
         if let Some(trophies) = &self.trophies {
             let panes = trophies.iter().map(|app_id| {
@@ -34,10 +35,19 @@ impl App {
                     text("Loading").into()
                 }
             });
+            // Games that gained achievements since they were completed have dropped out of the case
+            let schema_changes = column(self.schema_changes.iter().map(|c| {
+                let name = OWNED_GAMES.get(&c.app_id).map(|g| g.name.clone()).unwrap_or(c.app_id.to_string());
+                row![
+                    text(format!("New achievements added to {name}: {previous} -> {new} on {date}", previous = c.previous_total, new = c.new_total, date = c.detected_on)),
+                    button("Dismiss").on_press(Message::DismissSchemaChange(c.id)),
+                ].spacing(5).into()
+            })).spacing(5);
             column![
                 center_x(filter_games),
+                center_x(schema_changes),
                 scrollable(grid(panes).columns(10).spacing(10))
-            ].into()
+            ].spacing(10).into()
         }
         else {
             column![text("Loading trophy list...")].into()
@@ -45,29 +55,33 @@ impl App {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum TrophyCaseFilter {
     #[default]
     Completed,
     Perfected,
 }
 
-pub async fn load_trophies(view: TrophyCaseFilter) -> Vec<i32> {
+pub async fn load_trophies(view: TrophyCaseFilter, policy: CompletionPolicy, tag: Option<String>) -> Vec<i32> {
+    let tagged_set: HashSet<i32> = goals::filter_to_collection(OWNED_GAMES.values().cloned().collect(), &tag)
+        .iter()
+        .map(|g| g.appid)
+        .collect();
     let target_set: HashSet<i32> = game_target_store::get_game_targets().expect("Failed to load targets")
         .iter()
         .filter(|t| !t.complete)
         .map(|t| t.app_id)
         .collect();
-    game_completion_cache::get_game_completion()
-        .expect("Failed to load cache")
-        .iter()
-        .filter(|c| {
+    completion::get_game_progress(policy)
+        .values()
+        .filter(|p| tagged_set.contains(&p.completion.app_id))
+        .filter(|p| {
             match view {
-                TrophyCaseFilter::Completed => c.complete == 100 && !target_set.contains(&c.app_id),
-                TrophyCaseFilter::Perfected => c.perfect && !target_set.contains(&c.app_id),
+                TrophyCaseFilter::Completed => p.complete && !target_set.contains(&p.completion.app_id),
+                TrophyCaseFilter::Perfected => p.perfect && !target_set.contains(&p.completion.app_id),
             }
         }) 
-        .map(|c| c.app_id)
+        .map(|p| p.completion.app_id)
         .collect()
 }
 
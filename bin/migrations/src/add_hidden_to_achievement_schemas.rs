@@ -0,0 +1,67 @@
+use rusqlite::{Connection, Result};
+use directories::{ProjectDirs};
+use std::fs;
+
+// The column to add and its definition
+const COLUMNS: [(&str, &str); 1] = [
+    ("hidden", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+pub async fn run_migration() -> Result<String, String> {
+    let conn: Connection = get_connection();
+    // First create the table if it doesn't exist, this makes sure the migrations runs even if this is the first time running
+    let create_table = conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievement_schemas (
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            description TEXT,
+            icon TEXT NOT NULL,
+            icon_gray TEXT NOT NULL,
+            PRIMARY KEY (app_id, achievement_name)
+        )",
+        [], // No parameters needed
+    );
+    if create_table.is_err() {
+        return Err(create_table.err().unwrap().to_string());
+    }
+
+    for (column, definition) in COLUMNS {
+        // Check if the column is already there, so the migration can be re-run safely
+        let column_count = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('achievement_schemas') WHERE name = ?1",
+            [column],
+            |row| row.get::<_, i32>(0),
+        );
+        if column_count.is_err() {
+            return Err(column_count.err().unwrap().to_string());
+        }
+        if column_count.unwrap() > 0 {
+            println!("Column {column} already exists");
+            continue;
+        }
+
+        let add_column = conn.execute(
+            &format!("ALTER TABLE achievement_schemas ADD COLUMN {column} {definition}"),
+            [], // No parameters needed
+        );
+        if add_column.is_err() {
+            return Err(add_column.err().unwrap().to_string());
+        }
+        println!("Added {column} column");
+    }
+
+    Ok("Success".to_string())
+}
+
+fn get_connection() -> Connection {
+    let binding = ProjectDirs::from("com", "everest", "steam_randomiser")
+        .expect("Failed to get project directories");
+    let data_dir =  binding.data_local_dir();
+    if !fs::exists(data_dir).expect("Failed to check for directory") {
+        fs::create_dir(data_dir).expect("Failed to create directory");
+    }
+    let path = data_dir.join("steam_randomiser_database.db");
+    let conn: Connection = Connection::open(path).expect("Failed to open a connection");
+    conn
+}
@@ -0,0 +1,78 @@
+use rusqlite::{Connection, Result};
+use directories::{ProjectDirs};
+use std::fs;
+
+// The table and the name of the unique index to add on (app_id, achievement_name)
+const TABLES: [(&str, &str); 2] = [
+    ("steam_achievements_v_2", "steam_achievements_v_2_app_achievement"),
+    ("excluded_steam_achievements", "excluded_steam_achievements_app_achievement"),
+];
+
+pub async fn run_migration() -> Result<String, String> {
+    let conn: Connection = get_connection();
+    // First create the tables if they don't exist with the columns the stores expect, this makes sure the migrations runs even if this is the first time running
+    let create_goals_table = conn.execute(
+        "CREATE TABLE IF NOT EXISTS steam_achievements_v_2 (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            achievement_name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            app_id INTEGER NOT NULL,
+            description TEXT,
+            last_played INTEGER NOT NULL,
+            priority INTEGER NOT NULL DEFAULT 0,
+            due_date TEXT,
+            seed INTEGER
+        )",
+        [], // No parameters needed
+    );
+    if create_goals_table.is_err() {
+        return Err(create_goals_table.err().unwrap().to_string());
+    }
+    let create_exclusions_table = conn.execute(
+        "CREATE TABLE IF NOT EXISTS excluded_steam_achievements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            achievement_name TEXT NOT NULL,
+            app_id INTEGER NOT NULL,
+            reason TEXT
+        )",
+        [], // No parameters needed
+    );
+    if create_exclusions_table.is_err() {
+        return Err(create_exclusions_table.err().unwrap().to_string());
+    }
+
+    for (table, index) in TABLES {
+        // Keep the oldest row of each duplicate
+        let delete_duplicates = conn.execute(
+            &format!("DELETE FROM {table} WHERE id NOT IN (SELECT MIN(id) FROM {table} GROUP BY app_id, achievement_name)"),
+            [], // No parameters needed
+        );
+        if delete_duplicates.is_err() {
+            return Err(delete_duplicates.err().unwrap().to_string());
+        }
+        println!("Removed {count} duplicates from {table}", count = delete_duplicates.unwrap());
+
+        let create_index = conn.execute(
+            &format!("CREATE UNIQUE INDEX IF NOT EXISTS {index} ON {table} (app_id, achievement_name)"),
+            [], // No parameters needed
+        );
+        if create_index.is_err() {
+            return Err(create_index.err().unwrap().to_string());
+        }
+        println!("Added unique index to {table}");
+    }
+
+    Ok("Success".to_string())
+}
+
+fn get_connection() -> Connection {
+    let binding = ProjectDirs::from("com", "everest", "steam_randomiser")
+        .expect("Failed to get project directories");
+    let data_dir =  binding.data_local_dir();
+    if !fs::exists(data_dir).expect("Failed to check for directory") {
+        fs::create_dir(data_dir).expect("Failed to create directory");
+    }
+    let path = data_dir.join("steam_randomiser_database.db");
+    let conn: Connection = Connection::open(path).expect("Failed to open a connection");
+    conn
+}
@@ -0,0 +1,81 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+use directories::{ProjectDirs};
+use std::fs;
+
+// Saved in the settings once the patterns are converted, so a re-run doesn't convert them twice
+const CONVERTED_SETTING: &str = "exclusion_rule_patterns_are_regex";
+
+pub async fn run_migration() -> Result<String, String> {
+    let mut conn: Connection = get_connection();
+    // First create the tables if they don't exist, this makes sure the migrations runs even if this is the first time running
+    let create_tables = conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS exclusion_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            value TEXT NOT NULL,
+            reason TEXT
+        );
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );"
+    );
+    if create_tables.is_err() {
+        return Err(create_tables.err().unwrap().to_string());
+    }
+
+    let converted = conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [CONVERTED_SETTING],
+        |row| row.get::<_, String>(0),
+    ).optional();
+    if converted.is_err() {
+        return Err(converted.err().unwrap().to_string());
+    }
+    if converted.unwrap().is_some() {
+        println!("Patterns already converted");
+        return Ok("Success".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let patterns: Vec<(i32, String)> = {
+        let mut stmt = tx.prepare("SELECT id, value FROM exclusion_rules WHERE kind = 'pattern'").map_err(|e| e.to_string())?;
+        let iter = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?))).map_err(|e| e.to_string())?;
+        let mut vec : Vec<(i32, String)> = Vec::new();
+        for p in iter {
+            vec.push(p.map_err(|e| e.to_string())?);
+        }
+        vec
+    };
+    for (id, glob) in patterns {
+        // * matched any run of characters, everything else was matched as it is
+        let pattern = glob.split('*')
+            .map(regex::escape)
+            .collect::<Vec<String>>()
+            .join(".*");
+        tx.execute(
+            "UPDATE exclusion_rules SET value = ?1 WHERE id = ?2",
+            params![pattern, id],
+        ).map_err(|e| e.to_string())?;
+        println!("Converted rule {id} from {glob} to {pattern}");
+    }
+    tx.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, '1')",
+        [CONVERTED_SETTING],
+    ).map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())?;
+
+    Ok("Success".to_string())
+}
+
+fn get_connection() -> Connection {
+    let binding = ProjectDirs::from("com", "everest", "steam_randomiser")
+        .expect("Failed to get project directories");
+    let data_dir =  binding.data_local_dir();
+    if !fs::exists(data_dir).expect("Failed to check for directory") {
+        fs::create_dir(data_dir).expect("Failed to create directory");
+    }
+    let path = data_dir.join("steam_randomiser_database.db");
+    let conn: Connection = Connection::open(path).expect("Failed to open a connection");
+    conn
+}
@@ -1,5 +1,13 @@
 mod add_display_name_and_description_and_last_played_to_achievement_store;
 mod drop_key_store;
+mod add_reason_to_excluded_achievement_store;
+mod drop_game_completion_v_1;
+mod add_unique_goal_and_exclusion_constraints;
+mod add_priority_and_due_date_to_achievement_store;
+mod drop_request_count_v_1;
+mod add_seed_to_achievement_store;
+mod add_hidden_to_achievement_schemas;
+mod convert_exclusion_rule_patterns_to_regex;
 
 use clap::Parser;
 
@@ -47,6 +55,78 @@ async fn main() -> Result<(), reqwest::Error> {
                 println!("Success");
             }
         },
+        "add_reason_to_excluded_achievement_store" => {
+            let result = add_reason_to_excluded_achievement_store::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
+        "drop_game_completion_v_1" => {
+            let result = drop_game_completion_v_1::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
+        "add_unique_goal_and_exclusion_constraints" => {
+            let result = add_unique_goal_and_exclusion_constraints::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
+        "add_priority_and_due_date_to_achievement_store" => {
+            let result = add_priority_and_due_date_to_achievement_store::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
+        "drop_request_count_v_1" => {
+            let result = drop_request_count_v_1::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
+        "add_seed_to_achievement_store" => {
+            let result = add_seed_to_achievement_store::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
+        "add_hidden_to_achievement_schemas" => {
+            let result = add_hidden_to_achievement_schemas::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
+        "convert_exclusion_rule_patterns_to_regex" => {
+            let result = convert_exclusion_rule_patterns_to_regex::run_migration().await;
+            if result.is_err() {
+                println!("{error}", error = result.err().unwrap());
+            }
+            else {
+                println!("Success");
+            }
+        },
         &_ => println!("Enter a migration to run")
     };
     Ok(())
@@ -1,6 +1,6 @@
 use api::game_fetch;
-use db::{achievement_store, steam_id_store, game_completion_cache, excluded_achievement_store};
-use goals_lib::goals;
+use db::{achievement_store, steam_id_store, excluded_achievement_store};
+use goals_lib::{goals, completion, completion::GameProgress, roll::Roller, settings};
 
 use eframe::egui;
 use std::{env, collections::HashSet, collections::HashMap};
@@ -31,15 +31,11 @@ fn main() -> eframe::Result {
     let mut goals: Vec<achievement_store::Achievement> = get_goals();
 
     // Refresh the completed cache and fetch
-    runtime.block_on(goals::refresh_game_completion_cache(&key, &steam_id, &game_list));
-    let completed_games_cache: HashMap<i32, game_completion_cache::GameCompletion> = game_completion_cache::get_game_completion()
-        .expect("Failed to load completed games")
-        .iter()
-        .map(|n| (n.app_id, n.clone()))
-        .collect();
-
-    game_list.sort_by(|a,b| completed_games_cache.get(&b.appid).map(|f| f.complete).unwrap_or(0).cmp(
-        &completed_games_cache.get(&a.appid).map(|f| f.complete).unwrap_or(0)
+    runtime.block_on(goals::refresh_game_completion_cache(&key, &steam_id, &game_list)).expect("Failed to refresh game completion");
+    let completed_games_cache: HashMap<i32, GameProgress> = completion::get_game_progress(settings::get_completion_policy());
+
+    game_list.sort_by(|a,b| completed_games_cache.get(&b.appid).map(|f| f.progress).unwrap_or(0).cmp(
+        &completed_games_cache.get(&a.appid).map(|f| f.progress).unwrap_or(0)
     ));    
 
     // Actions
@@ -72,7 +68,7 @@ fn main() -> eframe::Result {
                     egui::ScrollArea::vertical().show(ui, |ui| {
                         ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
                             for g in &goals {
-                                let game_name = game_list.iter().find(|game| game.appid == g.app_id).unwrap().name.clone();
+                                let game_name = game_list.iter().find(|game| game.appid == g.app_id).map(|game| game.name.clone()).unwrap_or(g.app_id.to_string());
                                 let result = if  let Some(d) = &g.description {
                                     format!("{} : {} : {}", game_name, g.display_name.clone(), d.clone())
                                 }
@@ -85,7 +81,7 @@ fn main() -> eframe::Result {
                                     .clicked()
                                     && excluding_mode {
                                         achievement_store::delete_achievement(&g.id).expect("Failed to delete achievement");
-                                        excluded_achievement_store::save_excluded_achievement(&g.achievement_name, &g.app_id).expect("Failed to save excluded achievement");
+                                        excluded_achievement_store::save_excluded_achievement(&g.achievement_name, &g.app_id, &None).expect("Failed to save excluded achievement");
                                         refresh = true;
                                     };
                                 ui.add_space(5.0);
@@ -104,7 +100,7 @@ fn main() -> eframe::Result {
                     for s in game_list.iter().filter(|a: &&game_fetch::Game| selected_game_app_id.contains(&a.appid)) {
                         ui.add(egui::Label::new(s.name.clone()));
                         ui.add_space(5.0);
-                        let progress = completed_games_cache.get(&s.appid).map(|c| c.complete).unwrap_or(0).to_string();
+                        let progress = completed_games_cache.get(&s.appid).map(|c| c.progress).unwrap_or(0).to_string();
                         ui.add(egui::Label::new(format!("Progress [{}%]", progress)));
                         ui.add_space(5.0);
                         // Goals
@@ -121,10 +117,15 @@ fn main() -> eframe::Result {
                             ui.label(result);
                             ui.add_space(5.0);
                         }
-                        if ui.add(egui::Button::new("Random Achievement")).clicked() {
-                            let random_achievement = runtime.block_on(goals::get_random_achievement_for_game(&key, &steam_id, s));
+                        let goal_limit = goals::check_goal_limit(1).and_then(|_| goals::check_game_goal_limit(&s.appid));
+                        if let Err(limit) = goal_limit {
+                            ui.label(limit);
+                        }
+                        else if ui.add(egui::Button::new("Random Achievement")).clicked() {
+                            let mut roller = Roller::new(None);
+                            let random_achievement = runtime.block_on(goals::get_random_achievement_for_game(&key, &steam_id, s, roller.rng()));
                             if let Some(a) = random_achievement {
-                                achievement_store::save_achievement(&a.name, &a.display_name, &a.description, &s.appid, &s.last_played).expect("Failed to save achievement");
+                                achievement_store::save_achievement(&a.name, &a.display_name, &a.description, &s.appid, &s.last_played, &Some(roller.stored_seed())).expect("Failed to save achievement");
                                 refresh = true;
                             }
                         }
@@ -146,18 +147,18 @@ fn main() -> eframe::Result {
                         }
                         if ui.add(egui::RadioButton::new(sorting == Sorting::Progress, "Progress")).clicked() {
                             sorting = Sorting::Progress;
-                            game_list.sort_by(|a,b| completed_games_cache.get(&b.appid).map(|f| f.complete).unwrap_or(0).cmp(
-                                &completed_games_cache.get(&a.appid).map(|f| f.complete).unwrap_or(0)
+                            game_list.sort_by(|a,b| completed_games_cache.get(&b.appid).map(|f| f.progress).unwrap_or(0).cmp(
+                                &completed_games_cache.get(&a.appid).map(|f| f.progress).unwrap_or(0)
                             ));
                         }
                         for game in &mut game_list {
                             // check all filters
                             if filter_in_progress
-                                && completed_games_cache.get(&game.appid).map(|c| c.complete).unwrap_or(0) == 100 {
+                                && completed_games_cache.get(&game.appid).map(|c| c.progress).unwrap_or(0) == 100 {
                                     continue;
                                 }
                             if filter_completed_game
-                                && completed_games_cache.get(&game.appid).map(|c| c.complete).unwrap_or(0) != 100 {
+                                && completed_games_cache.get(&game.appid).map(|c| c.progress).unwrap_or(0) != 100 {
                                     continue;
                                 }
                             if filter_perfect
@@ -165,7 +166,7 @@ fn main() -> eframe::Result {
                                     continue;
                                 }
                             if filter_has_achievements
-                                && !completed_games_cache.get(&game.appid).map(|c| c.has_achievements).unwrap_or(true) {
+                                && !completed_games_cache.get(&game.appid).map(|c| c.completion.has_achievements).unwrap_or(true) {
                                     continue;
                                 }
                             if !filter_search.is_empty()
@@ -175,7 +176,7 @@ fn main() -> eframe::Result {
 
                             ui.add_space(5.0);
                             // Add a clickable game using egui::Label::sense()
-                            let progress = completed_games_cache.get(&game.appid).map(|c| c.complete).unwrap_or(0).to_string();
+                            let progress = completed_games_cache.get(&game.appid).map(|c| c.progress).unwrap_or(0).to_string();
                             if ui
                                 .add(egui::Label::new(format!("{name} : [{progress}%]", name = &game.name, progress = progress))
                                 .sense(egui::Sense::click()))
@@ -202,7 +203,6 @@ fn main() -> eframe::Result {
 }
 
 fn get_goals() -> Vec<achievement_store::Achievement> {
-    let mut goals: Vec<achievement_store::Achievement> = achievement_store::get_achievements().expect("Failed to load achievements");
-    goals.sort_by(|a, b| i32::cmp(&a.app_id,&b.app_id));
+    let goals: Vec<achievement_store::Achievement> = achievement_store::get_achievements().expect("Failed to load achievements");
     goals
 }
\ No newline at end of file
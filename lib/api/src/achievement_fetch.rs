@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
-use db::request_store;
+use db::{achievement_schema_store, request_store};
+use std::time::Instant;
 
 // Player Achievements Request
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlayerAchievement {
     pub apiname: String,
     pub achieved: i32,
+    #[serde(default)]
+    pub unlocktime: i64, // Unix time, 0 when locked
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,6 +40,8 @@ pub struct GameAchievement {
     pub description: Option<String>,
     pub icon: String,
     pub icongray: String,
+    #[serde(default)]
+    pub hidden: i32, // 1 when the description is hidden until unlocked
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,62 +61,81 @@ struct GameSchemaResponse {
 }
 
 pub async fn get_player_achievements(key : &str, steam_id : &str, app_id : &i32) -> Option<PlayerAchievements> {
+    try_get_player_achievements(key, steam_id, app_id).await
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The same as get_player_achievements but returns an error instead of panicking, for callers that can stop cleanly
+pub async fn try_get_player_achievements(key : &str, steam_id : &str, app_id : &i32) -> Result<Option<PlayerAchievements>, String> {
     let get_player_achievements_request: String = "https://api.steampowered.com/ISteamUserStats/GetPlayerAchievements/v1/?".to_owned()
         + "&key=" + key + "&steamid=" + steam_id
         + "&appid=" + &app_id.to_string();
 
-    if !request_store::increment().unwrap() {
-        panic!("Hit request limit, wait until tomorrow");
+    if !request_store::has_budget().map_err(|e| e.to_string())? {
+        return Err("Hit request limit, wait until tomorrow".to_string());
     }
+    let start = Instant::now();
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
         .get(get_player_achievements_request)
         .send()
         .await;
+    request_store::log_request("GetPlayerAchievements", &Some(*app_id), &req.as_ref().ok().map(|r| r.status().as_u16()), &(start.elapsed().as_millis() as i64)).map_err(|e| e.to_string())?;
 
-    if req.is_err() {
-        panic!()
-    }
-
-    let response:Result<PlayerStatsResponse, reqwest::Error>  = req.unwrap().json().await;
+    let response: PlayerStatsResponse = req.map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
 
-    if response.is_err() {
-        panic!()
-    }
-
-    let val = response.unwrap();
     // Success code can be true but no achievements present, typically for more modern games (Dota 2 is an example app_id 570)
-    if val.playerstats.success && let Some(a) = val.playerstats.achievements && let Some(n) = val.playerstats.game_name {
-        Option::Some(PlayerAchievements {
+    if response.playerstats.success && let Some(a) = response.playerstats.achievements && let Some(n) = response.playerstats.game_name {
+        Ok(Option::Some(PlayerAchievements {
             achievements: a,
             game_name: n,
-        })
+        }))
     }
     else {
-        Option::None
+        Ok(Option::None)
     }
 }
 
 pub async fn get_game_achievements(key : &str, app_id : &i32) -> Vec<GameAchievement> {
+    try_get_game_achievements(key, app_id).await
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The same as get_game_achievements but returns an error instead of panicking, for callers that can stop cleanly
+pub async fn try_get_game_achievements(key : &str, app_id : &i32) -> Result<Vec<GameAchievement>, String> {
     let get_schema_for_game_request: String =
         "https://api.steampowered.com/ISteamUserStats/GetSchemaForGame/v2/?key=".to_owned() + key + "&appid=" + &app_id.to_string();
 
-    if !request_store::increment().unwrap() {
-        panic!("Hit request limit, wait until tomorrow");
+    if !request_store::has_budget().map_err(|e| e.to_string())? {
+        return Err("Hit request limit, wait until tomorrow".to_string());
     }
+    let start = Instant::now();
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
         .get(get_schema_for_game_request)
         .send()
         .await;
-
-    if req.is_err() {
-        panic!()
-    }
-
-    let response:Result<GameSchemaResponse, reqwest::Error>  = req.unwrap().json().await;
-
-    if response.is_err() {
-        panic!()
-    }
-
-    response.unwrap().game.available_game_stats.map(|s| s.achievements).unwrap_or(vec![])
+    request_store::log_request("GetSchemaForGame", &Some(*app_id), &req.as_ref().ok().map(|r| r.status().as_u16()), &(start.elapsed().as_millis() as i64)).map_err(|e| e.to_string())?;
+
+    let response: GameSchemaResponse = req.map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let achievements = response.game.available_game_stats.map(|s| s.achievements).unwrap_or(vec![]);
+    // Keep a copy of every schema fetched for searching
+    let schema: Vec<achievement_schema_store::SchemaAchievement> = achievements.iter()
+        .map(|a| achievement_schema_store::SchemaAchievement {
+            app_id: *app_id,
+            achievement_name: a.name.clone(),
+            display_name: a.display_name.clone(),
+            description: a.description.clone(),
+            icon: a.icon.clone(),
+            icon_gray: a.icongray.clone(),
+            hidden: a.hidden == 1,
+        })
+        .collect();
+    achievement_schema_store::save_schema_for_app(app_id, &schema).map_err(|e| e.to_string())?;
+    Ok(achievements)
 }
\ No newline at end of file
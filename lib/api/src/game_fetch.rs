@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use db::request_store;
+use std::time::Instant;
 
 // Owned Games Request
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -27,13 +28,15 @@ pub async fn get_owned_games(key : &str, steam_id : &str) -> Vec<Game> {
 
     let get_owned_games_request: String =
         "https://api.steampowered.com/IPlayerService/GetOwnedGames/v1/?format=json&include_appinfo=true&include_played_free_games=true".to_owned() + "&key=" + key + "&steamid=" + steam_id;
-    if !request_store::increment().unwrap() {
+    if !request_store::has_budget().unwrap() {
         panic!("Hit request limit, wait until tomorrow");
     }
+    let start = Instant::now();
     let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
         .get(get_owned_games_request)
         .send()
         .await;
+    request_store::log_request("GetOwnedGames", &None, &req.as_ref().ok().map(|r| r.status().as_u16()), &(start.elapsed().as_millis() as i64)).unwrap();
 
     if req.is_err() {
         panic!()
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use db::{achievement_rarity_store, request_store};
+use std::time::Instant;
+
+// Global Achievement Percentages Request
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GlobalAchievementPercentage {
+    pub name: String,
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GlobalAchievementInternal {
+    name: String,
+    percent: serde_json::Value, // Steam sends this as a number for some apps and a string for others
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GlobalAchievements {
+    achievements: Vec<GlobalAchievementInternal>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GlobalAchievementPercentagesResponse {
+    achievementpercentages: GlobalAchievements,
+}
+
+/// Fetch the share of players that have each achievement, apps without stats return an empty list.
+/// Every response is cached so rarity can be looked up without another request
+pub async fn try_get_global_achievement_percentages(app_id : &i32) -> Result<Vec<GlobalAchievementPercentage>, String> {
+    let get_global_percentages_request: String =
+        "https://api.steampowered.com/ISteamUserStats/GetGlobalAchievementPercentagesForApp/v2/?gameid=".to_owned() + &app_id.to_string();
+
+    if !request_store::has_budget().map_err(|e| e.to_string())? {
+        return Err("Hit request limit, wait until tomorrow".to_string());
+    }
+    let start = Instant::now();
+    let req: Result<reqwest::Response, reqwest::Error> = reqwest::Client::new()
+        .get(get_global_percentages_request)
+        .send()
+        .await;
+    request_store::log_request("GetGlobalAchievementPercentagesForApp", &Some(*app_id), &req.as_ref().ok().map(|r| r.status().as_u16()), &(start.elapsed().as_millis() as i64)).map_err(|e| e.to_string())?;
+
+    let res = req.map_err(|e| e.to_string())?;
+    let percentages: Vec<GlobalAchievementPercentage> = if res.status().is_success() {
+        let response: GlobalAchievementPercentagesResponse = res.json()
+            .await
+            .map_err(|e| e.to_string())?;
+        response.achievementpercentages.achievements.into_iter()
+            .filter_map(|a| {
+                let percent = match &a.percent {
+                    serde_json::Value::Number(n) => n.as_f64(),
+                    serde_json::Value::String(s) => s.parse().ok(),
+                    _ => None,
+                };
+                percent.map(|percent| GlobalAchievementPercentage { name: a.name, percent })
+            })
+            .collect()
+    }
+    else {
+        // Steam answers with an error status for apps that have no stats
+        Vec::new()
+    };
+
+    let rarity: Vec<achievement_rarity_store::AchievementRarity> = percentages.iter()
+        .map(|p| achievement_rarity_store::AchievementRarity {
+            app_id: *app_id,
+            achievement_name: p.name.clone(),
+            percent: p.percent,
+        })
+        .collect();
+    achievement_rarity_store::save_rarity_for_app(app_id, &rarity).map_err(|e| e.to_string())?;
+    Ok(percentages)
+}
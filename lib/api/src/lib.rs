@@ -1,3 +1,4 @@
 pub mod achievement_fetch;
 pub mod game_fetch;
-pub mod game_cover_fetch;
\ No newline at end of file
+pub mod game_cover_fetch;
+pub mod global_achievement_fetch;
\ No newline at end of file
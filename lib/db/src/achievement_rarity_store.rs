@@ -0,0 +1,115 @@
+use rusqlite::{params, Connection, Result};
+
+use db_lib::db_manager;
+
+/// The share of all players that have unlocked an achievement
+#[derive(Debug, Clone)]
+pub struct AchievementRarity {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub percent: f64,
+}
+
+pub fn get_rarity_for_app(app_id: &i32) -> Result<Vec<AchievementRarity>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, achievement_name, percent FROM achievement_rarity WHERE app_id = ?1")?;
+    let iter = stmt.query_map([app_id], |row| {
+        Ok(AchievementRarity {
+            app_id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            percent: row.get(2)?,
+        })
+    })?;
+
+    let mut vec : Vec<AchievementRarity> = Vec::new();
+    for r in iter {
+        vec.push(r?);
+    }
+    Ok(vec)
+}
+
+pub fn get_all_rarity() -> Result<Vec<AchievementRarity>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, achievement_name, percent FROM achievement_rarity")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(AchievementRarity {
+            app_id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            percent: row.get(2)?,
+        })
+    })?;
+
+    let mut vec : Vec<AchievementRarity> = Vec::new();
+    for r in iter {
+        vec.push(r?);
+    }
+    Ok(vec)
+}
+
+/// The apps that have rarity cached, including apps with no achievements
+pub fn get_cached_app_ids() -> Result<Vec<i32>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id FROM achievement_rarity_apps")?;
+    let iter = stmt.query_map([], |row| row.get(0))?;
+
+    let mut vec : Vec<i32> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+/// Replace the cached rarity for an app
+pub fn save_rarity_for_app(app_id: &i32, rarity: &[AchievementRarity]) -> Result<()> {
+    let mut conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM achievement_rarity WHERE app_id = ?1",
+        params![app_id],
+    )?;
+    for r in rarity {
+        tx.execute(
+            "INSERT INTO achievement_rarity (app_id, achievement_name, percent) VALUES (?1, ?2, ?3)
+                ON CONFLICT(app_id, achievement_name) DO UPDATE SET percent=?3",
+            params![app_id, r.achievement_name, r.percent],
+        )?;
+    }
+    tx.execute(
+        "INSERT INTO achievement_rarity_apps (app_id) VALUES (?1) ON CONFLICT(app_id) DO NOTHING",
+        params![app_id],
+    )?;
+    tx.commit()
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievement_rarity (
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT NOT NULL,
+            percent REAL NOT NULL,
+            PRIMARY KEY (app_id, achievement_name)
+        )",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievement_rarity_apps (
+            app_id INTEGER PRIMARY KEY
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
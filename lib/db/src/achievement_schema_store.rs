@@ -0,0 +1,217 @@
+use rusqlite::{params, Connection, Result};
+
+use db_lib::db_manager;
+
+#[derive(Debug, Clone)]
+pub struct SchemaAchievement {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub icon: String,
+    pub icon_gray: String,
+    pub hidden: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+}
+
+pub fn get_schema_for_app(app_id: &i32) -> Result<Vec<SchemaAchievement>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, achievement_name, display_name, description, icon, icon_gray, hidden FROM achievement_schemas WHERE app_id = ?1")?;
+    let iter = stmt.query_map([app_id], |row| {
+        Ok(SchemaAchievement {
+            app_id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            display_name: row.get(2)?,
+            description: row.get(3)?,
+            icon: row.get(4)?,
+            icon_gray: row.get(5)?,
+            hidden: row.get(6)?,
+        })
+    })?;
+
+    let mut vec : Vec<SchemaAchievement> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+/// Every cached schema
+pub fn get_schemas() -> Result<Vec<SchemaAchievement>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, achievement_name, display_name, description, icon, icon_gray, hidden FROM achievement_schemas")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(SchemaAchievement {
+            app_id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            display_name: row.get(2)?,
+            description: row.get(3)?,
+            icon: row.get(4)?,
+            icon_gray: row.get(5)?,
+            hidden: row.get(6)?,
+        })
+    })?;
+
+    let mut vec : Vec<SchemaAchievement> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+/// The apps that have a schema cached, including apps with no achievements
+pub fn get_cached_app_ids() -> Result<Vec<i32>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id FROM achievement_schema_apps")?;
+    let iter = stmt.query_map([], |row| row.get(0))?;
+
+    let mut vec : Vec<i32> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+/// Replace the cached schema for an app and its entries in the search index
+pub fn save_schema_for_app(app_id: &i32, achievements: &[SchemaAchievement]) -> Result<()> {
+    let mut conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM achievement_schemas WHERE app_id = ?1",
+        params![app_id],
+    )?;
+    tx.execute(
+        "DELETE FROM achievement_search WHERE app_id = ?1",
+        params![app_id],
+    )?;
+    for a in achievements {
+        tx.execute(
+            "INSERT INTO achievement_schemas (app_id, achievement_name, display_name, description, icon, icon_gray, hidden) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                ON CONFLICT(app_id, achievement_name) DO UPDATE SET display_name=?3, description=?4, icon=?5, icon_gray=?6, hidden=?7",
+            params![app_id, a.achievement_name, a.display_name, a.description, a.icon, a.icon_gray, a.hidden],
+        )?;
+        tx.execute(
+            "INSERT INTO achievement_search (app_id, achievement_name, display_name, description) VALUES (?1, ?2, ?3, ?4)",
+            params![app_id, a.achievement_name, a.display_name, a.description],
+        )?;
+    }
+    tx.execute(
+        "INSERT INTO achievement_schema_apps (app_id) VALUES (?1) ON CONFLICT(app_id) DO NOTHING",
+        params![app_id],
+    )?;
+    tx.commit()
+}
+
+/// Add a single achievement to the search index if it is not already there, used for goals from uncached schemas
+pub fn index_achievement(app_id: &i32, achievement_name: &str, display_name: &str, description: &Option<String>) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO achievement_search (app_id, achievement_name, display_name, description)
+            SELECT ?1, ?2, ?3, ?4 WHERE NOT EXISTS (SELECT 1 FROM achievement_search WHERE app_id = ?1 AND achievement_name = ?2)",
+        params![app_id, achievement_name, display_name, description],
+    )?;
+
+    Ok(())
+}
+
+/// The app id and achievement name of everything in the search index
+pub fn get_indexed_achievements() -> Result<Vec<(i32, String)>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, achievement_name FROM achievement_search")?;
+    let iter = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+
+    let mut vec : Vec<(i32, String)> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+/// Full text search of the display names and descriptions, best matches first.
+/// Each word of the query is matched as a prefix so "fish" finds "Fishing"
+pub fn search(query: &str) -> Result<Vec<SearchMatch>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    // Quote every word so characters in the query can't be read as FTS syntax
+    let match_query = query.split_whitespace()
+        .map(|w| format!("\"{}\"*", w.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ");
+    if match_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare("SELECT app_id, achievement_name, display_name, description FROM achievement_search WHERE achievement_search MATCH ?1 ORDER BY rank")?;
+    let iter = stmt.query_map([match_query], |row| {
+        Ok(SearchMatch {
+            app_id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            display_name: row.get(2)?,
+            description: row.get(3)?,
+        })
+    })?;
+
+    let mut vec : Vec<SearchMatch> = Vec::new();
+    for m in iter {
+        vec.push(m?);
+    }
+    Ok(vec)
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievement_schemas (
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            description TEXT,
+            icon TEXT NOT NULL,
+            icon_gray TEXT NOT NULL,
+            hidden INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (app_id, achievement_name)
+        )",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievement_schema_apps (
+            app_id INTEGER PRIMARY KEY
+        )",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS achievement_search USING fts5 (
+            app_id UNINDEXED,
+            achievement_name UNINDEXED,
+            display_name,
+            description
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, ErrorCode, Result};
+use chrono::NaiveDate;
 
 use db_lib::db_manager;
 
+use crate::achievement_schema_store;
+
+// Keeps one row per achievement, a database with duplicates from before it existed needs the migration add_unique_goal_and_exclusion_constraints
+const UNIQUE_INDEX: &str = "steam_achievements_v_2_app_achievement";
+
 pub struct Achievement {
     pub id: i32,
     pub achievement_name: String,
@@ -9,13 +15,33 @@ pub struct Achievement {
     pub app_id: i32,
     pub description: Option<String>,
     pub last_played: i64, 
+    pub priority: i32, // Higher is more important
+    pub due_date: Option<NaiveDate>,
+    pub seed: Option<i64>, // The seed of the roll that picked it, None for goals from before seeds were recorded
+}
+
+impl Achievement {
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.due_date.is_some_and(|d| d < today)
+    }
+
+    /// Due today or within the next number of days, overdue goals are not included
+    pub fn is_due_within(&self, today: NaiveDate, days: i64) -> bool {
+        self.due_date.is_some_and(|d| d >= today && (d - today).num_days() <= days)
+    }
 }
 
-pub fn get_achievement(id: &i32) -> Result<Achievement> {
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn parse_due_date(date: Option<String>) -> Option<NaiveDate> {
+    date.and_then(|d| NaiveDate::parse_from_str(&d, DATE_FORMAT).ok())
+}
+
+pub fn get_achievement(id: &i32) -> Result<Option<Achievement>> {
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
 
-    let mut stmt = conn.prepare("SELECT id, achievement_name, display_name, description, app_id, last_played FROM steam_achievements_v_2 WHERE id = ?1")?;
+    let mut stmt = conn.prepare("SELECT id, achievement_name, display_name, description, app_id, last_played, priority, due_date, seed FROM steam_achievements_v_2 WHERE id = ?1")?;
     let mut achieve_iter = stmt.query_map([id], |row| {
         Ok(Achievement {
             id: row.get(0)?,
@@ -24,17 +50,19 @@ pub fn get_achievement(id: &i32) -> Result<Achievement> {
             description: row.get(3)?,
             app_id: row.get(4)?,
             last_played: row.get(5)?,
+            priority: row.get(6)?,
+            due_date: parse_due_date(row.get(7)?),
+            seed: row.get(8)?,
         })
     })?;
-    let val = achieve_iter.next();
-    val.expect("Id not found")
+    achieve_iter.next().transpose()
 }
 
 pub fn get_achievements() -> Result<Vec<Achievement>> {
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
 
-    let mut stmt = conn.prepare("SELECT id, achievement_name, display_name, description, app_id, last_played FROM steam_achievements_v_2")?;
+    let mut stmt = conn.prepare("SELECT id, achievement_name, display_name, description, app_id, last_played, priority, due_date, seed FROM steam_achievements_v_2 ORDER BY priority DESC, due_date IS NULL, due_date, app_id")?;
     let achieve_iter = stmt.query_map([], |row| {
         Ok(Achievement {
             id: row.get(0)?,
@@ -43,6 +71,9 @@ pub fn get_achievements() -> Result<Vec<Achievement>> {
             description: row.get(3)?,
             app_id: row.get(4)?,
             last_played: row.get(5)?,
+            priority: row.get(6)?,
+            due_date: parse_due_date(row.get(7)?),
+            seed: row.get(8)?,
         })
     })?;
 
@@ -57,7 +88,7 @@ pub fn get_achievements_for_app(app_id: &i32) -> Result<Vec<Achievement>> {
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
 
-    let mut stmt = conn.prepare("SELECT id, achievement_name, display_name, description, app_id, last_played FROM steam_achievements_v_2 WHERE app_id = ?1")?;
+    let mut stmt = conn.prepare("SELECT id, achievement_name, display_name, description, app_id, last_played, priority, due_date, seed FROM steam_achievements_v_2 WHERE app_id = ?1 ORDER BY priority DESC, due_date IS NULL, due_date")?;
     let achieve_iter = stmt.query_map([app_id], |row| {
         Ok(Achievement {
             id: row.get(0)?,
@@ -66,6 +97,9 @@ pub fn get_achievements_for_app(app_id: &i32) -> Result<Vec<Achievement>> {
             description: row.get(3)?,
             app_id: row.get(4)?,
             last_played: row.get(5)?,
+            priority: row.get(6)?,
+            due_date: parse_due_date(row.get(7)?),
+            seed: row.get(8)?,
         })
     })?;
 
@@ -76,15 +110,30 @@ pub fn get_achievements_for_app(app_id: &i32) -> Result<Vec<Achievement>> {
     Ok(achievement_vec)
 }
 
-pub fn save_achievement(achievement_name: &String, display_name: &String, description: &Option<String>, app_id: &i32, last_played: &i64) -> Result<()> {
+/// The number of goals, only counting the game given when there is one
+pub fn count_achievements(app_id: Option<&i32>) -> Result<i32> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    match app_id {
+        Some(app_id) => conn.query_row("SELECT COUNT(*) FROM steam_achievements_v_2 WHERE app_id = ?1", [app_id], |row| row.get(0)),
+        None => conn.query_row("SELECT COUNT(*) FROM steam_achievements_v_2", [], |row| row.get(0)),
+    }
+}
+
+pub fn save_achievement(achievement_name: &String, display_name: &String, description: &Option<String>, app_id: &i32, last_played: &i64, seed: &Option<i64>) -> Result<()> {
     // Connect to SQLite database (creates the file if it doesn't exist)
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
     
-    // Add in the achievement
+    // Goals can come from schemas that were fetched before they were cached, so they are added to the search index here
+    achievement_schema_store::index_achievement(app_id, achievement_name, display_name, description)?;
+
+    // Add in the achievement, saving it again only refreshes the details
     conn.execute(
-        "INSERT INTO steam_achievements_v_2 (achievement_name, display_name, description, app_id, last_played) VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![achievement_name, display_name, description, app_id, last_played],
+        "INSERT INTO steam_achievements_v_2 (achievement_name, display_name, description, app_id, last_played, seed) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(app_id, achievement_name) DO UPDATE SET display_name=?2, description=?3",
+        params![achievement_name, display_name, description, app_id, last_played, seed],
     )?;
 
     Ok(())
@@ -102,6 +151,30 @@ pub fn update_last_played(id: &i32, last_played: &i64) -> Result<()> {
     Ok(())
 }
 
+pub fn update_priority(id: &i32, priority: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "UPDATE steam_achievements_v_2 SET priority = ?1 WHERE id = ?2",
+        params![priority, id],
+    )?;
+
+    Ok(())
+}
+
+pub fn update_due_date(id: &i32, due_date: &Option<NaiveDate>) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "UPDATE steam_achievements_v_2 SET due_date = ?1 WHERE id = ?2",
+        params![due_date.map(|d| d.format(DATE_FORMAT).to_string()), id],
+    )?;
+
+    Ok(())
+}
+
 pub fn delete_achievement(id: &i32) -> Result<()> {
     // Connect to SQLite database (creates the file if it doesn't exist)
     let conn: Connection = db_manager::get_connection();
@@ -115,6 +188,23 @@ pub fn delete_achievement(id: &i32) -> Result<()> {
     Ok(())
 }
 
+pub fn delete_all_achievements() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM steam_achievements_v_2",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
 fn create_table(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS steam_achievements_v_2 (
@@ -123,10 +213,19 @@ fn create_table(conn: &Connection) -> Result<()> {
             display_name TEXT NOT NULL,
             app_id INTEGER NOT NULL,
             description TEXT,
-            last_played INTEGER NOT NULL
+            last_played INTEGER NOT NULL,
+            priority INTEGER NOT NULL DEFAULT 0,
+            due_date TEXT,
+            seed INTEGER
         )",
         [], // No parameters needed
     )?;
-
-    Ok(())
+    match conn.execute(
+        &format!("CREATE UNIQUE INDEX IF NOT EXISTS {UNIQUE_INDEX} ON steam_achievements_v_2 (app_id, achievement_name)"),
+        [], // No parameters needed
+    ) {
+        // Duplicates saved before the index existed stop it being added, the doctor reports them until the migration removes them
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::ConstraintViolation => Ok(()),
+        result => result.map(|_| ()),
+    }
 }
\ No newline at end of file
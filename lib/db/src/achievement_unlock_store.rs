@@ -0,0 +1,95 @@
+use rusqlite::{params, Connection, Result};
+
+use db_lib::db_manager;
+
+/// An achievement the player has unlocked, unlocked_at is the unix time Steam reports
+#[derive(Debug, Clone)]
+pub struct AchievementUnlock {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub unlocked_at: i64,
+}
+
+pub fn get_unlocks() -> Result<Vec<AchievementUnlock>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, achievement_name, unlocked_at FROM achievement_unlocks")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(AchievementUnlock {
+            app_id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            unlocked_at: row.get(2)?,
+        })
+    })?;
+
+    let mut vec : Vec<AchievementUnlock> = Vec::new();
+    for u in iter {
+        vec.push(u?);
+    }
+    Ok(vec)
+}
+
+/// The apps that have unlocks cached, including apps with nothing unlocked
+pub fn get_cached_app_ids() -> Result<Vec<i32>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id FROM achievement_unlock_apps")?;
+    let iter = stmt.query_map([], |row| row.get(0))?;
+
+    let mut vec : Vec<i32> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+/// Replace the cached unlocks for an app
+pub fn save_unlocks_for_app(app_id: &i32, unlocks: &[AchievementUnlock]) -> Result<()> {
+    let mut conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "DELETE FROM achievement_unlocks WHERE app_id = ?1",
+        params![app_id],
+    )?;
+    for u in unlocks {
+        tx.execute(
+            "INSERT INTO achievement_unlocks (app_id, achievement_name, unlocked_at) VALUES (?1, ?2, ?3)
+                ON CONFLICT(app_id, achievement_name) DO UPDATE SET unlocked_at=?3",
+            params![app_id, u.achievement_name, u.unlocked_at],
+        )?;
+    }
+    tx.execute(
+        "INSERT INTO achievement_unlock_apps (app_id) VALUES (?1) ON CONFLICT(app_id) DO NOTHING",
+        params![app_id],
+    )?;
+    tx.commit()
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievement_unlocks (
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT NOT NULL,
+            unlocked_at INTEGER NOT NULL,
+            PRIMARY KEY (app_id, achievement_name)
+        )",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS achievement_unlock_apps (
+            app_id INTEGER PRIMARY KEY
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use rusqlite::{params, Connection, Result};
+
+use db_lib::db_manager;
+
+pub struct Collection {
+    pub id: i32,
+    pub name: String,
+}
+
+pub struct CollectionGame {
+    pub collection_id: i32,
+    pub app_id: i32,
+}
+
+pub fn get_collections() -> Result<Vec<Collection>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, name FROM collections ORDER BY name")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(Collection {
+            id: row.get(0)?,
+            name: row.get(1)?,
+        })
+    })?;
+
+    let mut vec : Vec<Collection> = Vec::new();
+    for c in iter {
+        vec.push(c?);
+    }
+    Ok(vec)
+}
+
+pub fn get_collection(name: &str) -> Result<Option<Collection>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, name FROM collections WHERE name = ?1 LIMIT 1")?;
+    let mut iter = stmt.query_map([name], |row| {
+        Ok(Collection {
+            id: row.get(0)?,
+            name: row.get(1)?,
+        })
+    })?;
+
+    iter.next().transpose()
+}
+
+/// Creates the collection if needed and returns its id
+pub fn save_collection(name: &str) -> Result<i32> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO collections (name) VALUES (?1) ON CONFLICT(name) DO NOTHING",
+        params![name],
+    )?;
+
+    conn.query_row("SELECT id FROM collections WHERE name = ?1", [name], |row| row.get(0))
+}
+
+pub fn delete_collection(id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM collection_games WHERE collection_id = ?1",
+        params![id],
+    )?;
+    conn.execute(
+        "DELETE FROM collections WHERE id = ?1",
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_collections() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM collection_games",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "DELETE FROM collections",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn get_collection_games() -> Result<Vec<CollectionGame>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT collection_id, app_id FROM collection_games")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(CollectionGame {
+            collection_id: row.get(0)?,
+            app_id: row.get(1)?,
+        })
+    })?;
+
+    let mut vec : Vec<CollectionGame> = Vec::new();
+    for c in iter {
+        vec.push(c?);
+    }
+    Ok(vec)
+}
+
+pub fn get_app_ids_for_collection(collection_id: &i32) -> Result<Vec<i32>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id FROM collection_games WHERE collection_id = ?1")?;
+    let iter = stmt.query_map([collection_id], |row| row.get(0))?;
+
+    let mut vec : Vec<i32> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+pub fn get_collections_for_app(app_id: &i32) -> Result<Vec<Collection>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT c.id, c.name FROM collections c JOIN collection_games g ON g.collection_id = c.id WHERE g.app_id = ?1 ORDER BY c.name")?;
+    let iter = stmt.query_map([app_id], |row| {
+        Ok(Collection {
+            id: row.get(0)?,
+            name: row.get(1)?,
+        })
+    })?;
+
+    let mut vec : Vec<Collection> = Vec::new();
+    for c in iter {
+        vec.push(c?);
+    }
+    Ok(vec)
+}
+
+pub fn add_game_to_collection(collection_id: &i32, app_id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO collection_games (collection_id, app_id) VALUES (?1, ?2) ON CONFLICT(collection_id, app_id) DO NOTHING",
+        params![collection_id, app_id],
+    )?;
+
+    Ok(())
+}
+
+pub fn remove_game_from_collection(collection_id: &i32, app_id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM collection_games WHERE collection_id = ?1 AND app_id = ?2",
+        params![collection_id, app_id],
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS collections (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE
+        )",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS collection_games (
+            collection_id INTEGER NOT NULL,
+            app_id INTEGER NOT NULL,
+            PRIMARY KEY (collection_id, app_id)
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use rusqlite::{params, Connection, Result};
+use chrono::NaiveDate;
+
+use db_lib::db_manager;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The achievement picked as the challenge for a day, only one is picked per day
+#[derive(Debug, Clone)]
+pub struct DailyChallenge {
+    pub date: NaiveDate,
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub completed: bool,
+}
+
+/// Every challenge, newest first
+pub fn get_daily_challenges() -> Result<Vec<DailyChallenge>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT date, app_id, achievement_name, display_name, description, completed FROM daily_challenges ORDER BY date DESC")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(DailyChallenge {
+            date: NaiveDate::parse_from_str(&row.get::<_, String>(0)?, DATE_FORMAT).unwrap_or_default(),
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            display_name: row.get(3)?,
+            description: row.get(4)?,
+            completed: row.get(5)?,
+        })
+    })?;
+
+    let mut vec : Vec<DailyChallenge> = Vec::new();
+    for c in iter {
+        vec.push(c?);
+    }
+    Ok(vec)
+}
+
+pub fn get_daily_challenge(date: &NaiveDate) -> Result<Option<DailyChallenge>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT date, app_id, achievement_name, display_name, description, completed FROM daily_challenges WHERE date = ?1")?;
+    let mut iter = stmt.query_map([date.format(DATE_FORMAT).to_string()], |row| {
+        Ok(DailyChallenge {
+            date: NaiveDate::parse_from_str(&row.get::<_, String>(0)?, DATE_FORMAT).unwrap_or_default(),
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            display_name: row.get(3)?,
+            description: row.get(4)?,
+            completed: row.get(5)?,
+        })
+    })?;
+    iter.next().transpose()
+}
+
+/// Saving a challenge for a day that already has one keeps the first, so the day's pick never changes
+pub fn save_daily_challenge(challenge: &DailyChallenge) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO daily_challenges (date, app_id, achievement_name, display_name, description, completed) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(date) DO NOTHING",
+        params![
+            challenge.date.format(DATE_FORMAT).to_string(),
+            challenge.app_id,
+            challenge.achievement_name,
+            challenge.display_name,
+            challenge.description,
+            challenge.completed,
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn complete_daily_challenge(date: &NaiveDate) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "UPDATE daily_challenges SET completed = 1 WHERE date = ?1",
+        params![date.format(DATE_FORMAT).to_string()],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_daily_challenges() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM daily_challenges",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS daily_challenges (
+            date TEXT PRIMARY KEY,
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            description TEXT,
+            completed INTEGER NOT NULL DEFAULT 0
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
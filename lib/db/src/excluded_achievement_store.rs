@@ -1,23 +1,117 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, ErrorCode, Result};
+use std::{fmt, str::FromStr};
 
 use db_lib::db_manager;
 
+// Keeps one row per achievement, a database with duplicates from before it existed needs the migration add_unique_goal_and_exclusion_constraints
+const UNIQUE_INDEX: &str = "excluded_steam_achievements_app_achievement";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExclusionReason {
+    Multiplayer,
+    Dlc,
+    Bugged,
+    Missable,
+}
+
+impl ExclusionReason {
+    pub const ALL: [ExclusionReason; 4] = [
+        ExclusionReason::Multiplayer,
+        ExclusionReason::Dlc,
+        ExclusionReason::Bugged,
+        ExclusionReason::Missable,
+    ];
+
+    // The value saved in the database
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            ExclusionReason::Multiplayer => "multiplayer",
+            ExclusionReason::Dlc => "dlc",
+            ExclusionReason::Bugged => "bugged",
+            ExclusionReason::Missable => "missable",
+        }
+    }
+}
+
+impl fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExclusionReason::Multiplayer => write!(f, "Multiplayer"),
+            ExclusionReason::Dlc => write!(f, "DLC"),
+            ExclusionReason::Bugged => write!(f, "Bugged"),
+            ExclusionReason::Missable => write!(f, "Missable"),
+        }
+    }
+}
+
+impl FromStr for ExclusionReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lowercase = s.trim().to_lowercase();
+        ExclusionReason::ALL.iter()
+            .find(|r| r.as_str() == lowercase)
+            .copied()
+            .ok_or(format!("Unknown reason {s}, use one of multiplayer, dlc, bugged or missable"))
+    }
+}
+
 pub struct ExcludedAchievement {
     pub id: i32,
     pub achievement_name: String,
     pub app_id: i32,
+    pub reason: Option<ExclusionReason>,
+}
+
+pub fn get_excluded_achievements() -> Result<Vec<ExcludedAchievement>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, achievement_name, app_id, reason FROM excluded_steam_achievements ORDER BY app_id")?;
+    let achieve_iter = stmt.query_map([], |row| {
+        Ok(ExcludedAchievement {
+            id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            app_id: row.get(2)?,
+            reason: parse_reason(row.get(3)?),
+        })
+    })?;
+
+    let mut achievement_vec : Vec<ExcludedAchievement> = Vec::new();
+    for d in achieve_iter {
+        achievement_vec.push(d.unwrap());
+    }
+    Ok(achievement_vec)
+}
+
+pub fn get_excluded_achievement(id: &i32) -> Result<Option<ExcludedAchievement>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, achievement_name, app_id, reason FROM excluded_steam_achievements WHERE id = ?1 LIMIT 1")?;
+    let mut achieve_iter = stmt.query_map([id], |row| {
+        Ok(ExcludedAchievement {
+            id: row.get(0)?,
+            achievement_name: row.get(1)?,
+            app_id: row.get(2)?,
+            reason: parse_reason(row.get(3)?),
+        })
+    })?;
+
+    achieve_iter.next().transpose()
 }
 
 pub fn get_excluded_achievements_for_app(app_id: &i32) -> Result<Vec<ExcludedAchievement>> {
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
 
-    let mut stmt = conn.prepare("SELECT id, achievement_name, app_id FROM excluded_steam_achievements WHERE app_id = ?1")?;
+    let mut stmt = conn.prepare("SELECT id, achievement_name, app_id, reason FROM excluded_steam_achievements WHERE app_id = ?1")?;
     let achieve_iter = stmt.query_map([app_id], |row| {
         Ok(ExcludedAchievement {
             id: row.get(0)?,
             achievement_name: row.get(1)?,
             app_id: row.get(2)?,
+            reason: parse_reason(row.get(3)?),
         })
     })?;
 
@@ -28,29 +122,95 @@ pub fn get_excluded_achievements_for_app(app_id: &i32) -> Result<Vec<ExcludedAch
     Ok(achievement_vec)
 }
 
-pub fn save_excluded_achievement(achievement_name: &String, app_id: &i32) -> Result<()> {
+pub fn save_excluded_achievement(achievement_name: &String, app_id: &i32, reason: &Option<ExclusionReason>) -> Result<()> {
     // Connect to SQLite database (creates the file if it doesn't exist)
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
-    
-    // Add in the achievement
+
+    // Add in the achievement, excluding it again only replaces the reason if one is given
     conn.execute(
-        "INSERT INTO excluded_steam_achievements (achievement_name, app_id) VALUES (?1, ?2)",
-        params![achievement_name, app_id],
+        "INSERT INTO excluded_steam_achievements (achievement_name, app_id, reason) VALUES (?1, ?2, ?3)
+            ON CONFLICT(app_id, achievement_name) DO UPDATE SET reason=COALESCE(?3, reason)",
+        params![achievement_name, app_id, reason.map(|r| r.as_str())],
     )?;
 
     Ok(())
 }
 
+pub fn update_excluded_achievement_reason(id: &i32, reason: &Option<ExclusionReason>) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "UPDATE excluded_steam_achievements SET reason = ?1 WHERE id = ?2",
+        params![reason.map(|r| r.as_str()), id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_excluded_achievement(id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM excluded_steam_achievements WHERE id = ?1",
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_excluded_achievements_for_app(app_id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM excluded_steam_achievements WHERE app_id = ?1",
+        params![app_id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_excluded_achievements() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM excluded_steam_achievements",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+fn parse_reason(reason: Option<String>) -> Option<ExclusionReason> {
+    // Unknown values are treated as no reason rather than failing the whole read
+    reason.and_then(|r| r.parse().ok())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
 fn create_table(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS excluded_steam_achievements (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             achievement_name TEXT NOT NULL,
-            app_id INTEGER NOT NULL
+            app_id INTEGER NOT NULL,
+            reason TEXT
         )",
         [], // No parameters needed
     )?;
-
-    Ok(())
-}
\ No newline at end of file
+    match conn.execute(
+        &format!("CREATE UNIQUE INDEX IF NOT EXISTS {UNIQUE_INDEX} ON excluded_steam_achievements (app_id, achievement_name)"),
+        [], // No parameters needed
+    ) {
+        // Duplicates saved before the index existed stop it being added, the doctor reports them until the migration removes them
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::ConstraintViolation => Ok(()),
+        result => result.map(|_| ()),
+    }
+}
@@ -0,0 +1,155 @@
+use rusqlite::{params, Connection, Result};
+use std::{fmt, str::FromStr};
+
+use db_lib::db_manager;
+
+use crate::excluded_achievement_store::ExclusionReason;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuleKind {
+    /// The value is matched against the name, display name and description
+    Pattern,
+    /// The value is a percent, achievements fewer players have are matched
+    Rarity,
+    /// Achievements hidden until unlocked, there is no value
+    Hidden,
+    /// The value is an app id, every achievement in the game is matched
+    App,
+}
+
+impl RuleKind {
+    pub const ALL: [RuleKind; 4] = [
+        RuleKind::Pattern,
+        RuleKind::Rarity,
+        RuleKind::Hidden,
+        RuleKind::App,
+    ];
+
+    // The value saved in the database
+    fn as_str(&self) -> &'static str {
+        match self {
+            RuleKind::Pattern => "pattern",
+            RuleKind::Rarity => "rarity",
+            RuleKind::Hidden => "hidden",
+            RuleKind::App => "app",
+        }
+    }
+}
+
+impl fmt::Display for RuleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleKind::Pattern => write!(f, "Pattern"),
+            RuleKind::Rarity => write!(f, "Rarity"),
+            RuleKind::Hidden => write!(f, "Hidden"),
+            RuleKind::App => write!(f, "App"),
+        }
+    }
+}
+
+impl FromStr for RuleKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lowercase = s.trim().to_lowercase();
+        RuleKind::ALL.iter()
+            .find(|k| k.as_str() == lowercase)
+            .copied()
+            .ok_or(format!("Unknown rule {s}, use one of pattern, rarity, hidden or app"))
+    }
+}
+
+/// Excludes every achievement it matches without listing them one by one
+#[derive(Debug, Clone)]
+pub struct ExclusionRule {
+    pub id: i32,
+    pub kind: RuleKind,
+    pub value: String,
+    pub reason: Option<ExclusionReason>,
+}
+
+pub fn get_exclusion_rules() -> Result<Vec<ExclusionRule>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, kind, value, reason FROM exclusion_rules ORDER BY id")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(ExclusionRule {
+            id: row.get(0)?,
+            kind: row.get::<_, String>(1)?.parse().unwrap_or(RuleKind::Pattern),
+            value: row.get(2)?,
+            // Unknown values are treated as no reason rather than failing the whole read
+            reason: row.get::<_, Option<String>>(3)?.and_then(|r| r.parse().ok()),
+        })
+    })?;
+
+    let mut vec : Vec<ExclusionRule> = Vec::new();
+    for r in iter {
+        vec.push(r?);
+    }
+    Ok(vec)
+}
+
+/// Returns the id of the new rule
+pub fn save_exclusion_rule(kind: &RuleKind, value: &str, reason: &Option<ExclusionReason>) -> Result<i32> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO exclusion_rules (kind, value, reason) VALUES (?1, ?2, ?3)",
+        params![kind.as_str(), value, reason.map(|r| r.as_str())],
+    )?;
+
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+pub fn delete_exclusion_rule(id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM exclusion_rules WHERE id = ?1",
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_exclusion_rules() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM exclusion_rules",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    if db_manager::table_exists("exclusion_rules")? {
+        return Ok(());
+    }
+    // Patterns saved in a new table are already regexes, the setting stops the migration convert_exclusion_rule_patterns_to_regex escaping them
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS exclusion_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            value TEXT NOT NULL,
+            reason TEXT
+        );
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        INSERT OR IGNORE INTO settings (key, value) VALUES ('exclusion_rule_patterns_are_regex', '1');"
+    )?;
+
+    Ok(())
+}
@@ -1,28 +1,93 @@
 use rusqlite::{params, Connection, Result};
+use std::{fmt, str::FromStr};
 
 use db_lib::db_manager;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CompletionFormula {
+    /// Achieved out of every achievement in the game
+    Raw,
+    /// Achieved out of every achievement that has not been excluded
+    #[default]
+    ExclusionAdjusted,
+}
+
+// Written the same way FromStr reads it
+impl fmt::Display for CompletionFormula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompletionFormula::Raw => write!(f, "raw"),
+            CompletionFormula::ExclusionAdjusted => write!(f, "adjusted"),
+        }
+    }
+}
+
+impl FromStr for CompletionFormula {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "raw" => Ok(CompletionFormula::Raw),
+            "adjusted" => Ok(CompletionFormula::ExclusionAdjusted),
+            _ => Err(format!("Unknown formula {s}, use one of raw or adjusted")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct GameCompletion {
     pub app_id: i32,
-    pub complete: i8,
+    pub total: i32,
+    pub achieved: i32,
+    pub excluded: i32, // Only counts excluded achievements that are not yet achieved
     pub last_played: i64,
     pub has_achievements: bool,
     pub perfect: bool,
 }
 
+impl GameCompletion {
+    pub fn percentage(&self, formula: CompletionFormula) -> i8 {
+        let denominator = match formula {
+            CompletionFormula::Raw => self.total,
+            CompletionFormula::ExclusionAdjusted => self.total - self.excluded,
+        };
+        if !self.has_achievements || self.total == 0 {
+            0
+        }
+        else if denominator <= 0 {
+            // Everything left has been excluded
+            100
+        }
+        else {
+            ((100 * self.achieved) / denominator) as i8
+        }
+    }
+
+    /// The raw counts, for example "47/52 (3 excluded)"
+    pub fn counts_display(&self) -> String {
+        if self.excluded > 0 {
+            format!("{}/{} ({} excluded)", self.achieved, self.total, self.excluded)
+        }
+        else {
+            format!("{}/{}", self.achieved, self.total)
+        }
+    }
+}
+
 pub fn get_game_completion() -> Result<Vec<GameCompletion>> {
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
 
-    let mut stmt = conn.prepare("SELECT app_id, complete, last_played, has_achievements, perfect FROM steam_game_completion")?;
+    let mut stmt = conn.prepare("SELECT app_id, total, achieved, excluded, last_played, has_achievements, perfect FROM steam_game_completion_v_2")?;
     let achieve_iter = stmt.query_map([], |row| {
         Ok(GameCompletion {
             app_id: row.get(0)?,
-            complete: row.get(1)?,
-            last_played: row.get(2)?,
-            has_achievements: row.get(3)?,
-            perfect: row.get(4)?,
+            total: row.get(1)?,
+            achieved: row.get(2)?,
+            excluded: row.get(3)?,
+            last_played: row.get(4)?,
+            has_achievements: row.get(5)?,
+            perfect: row.get(6)?,
         })
     })?;
 
@@ -33,37 +98,28 @@ pub fn get_game_completion() -> Result<Vec<GameCompletion>> {
     Ok(vec)
 }
 
-pub fn get_game_completion_above_or_equal(completed: i8) -> Result<Vec<GameCompletion>> {
+pub fn save_game_completion(app_id: &i32, total: i32, achieved: i32, excluded: i32, last_played: i64, has_achievements: bool, perfect: bool) -> Result<()> {
+    // Connect to SQLite database (creates the file if it doesn't exist)
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
 
-    let mut stmt = conn.prepare("SELECT app_id, complete, last_played, has_achievements, perfect FROM steam_game_completion WHERE complete >= ?1 AND has_achievements = true ORDER BY complete DESC")?;
-    let achieve_iter = stmt.query_map([completed], |row| {
-        Ok(GameCompletion {
-            app_id: row.get(0)?,
-            complete: row.get(1)?,
-            last_played: row.get(2)?,
-            has_achievements: row.get(3)?,
-            perfect: row.get(4)?,
-        })
-    })?;
+    // Add in the achievement
+    conn.execute(
+        "INSERT INTO steam_game_completion_v_2 (app_id, total, achieved, excluded, last_played, has_achievements, perfect) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            ON CONFLICT(app_id) DO UPDATE SET total=?2, achieved=?3, excluded=?4, last_played=?5, has_achievements=?6, perfect=?7",
+        params![app_id, total, achieved, excluded, last_played, has_achievements, perfect],
+    )?;
 
-    let mut vec : Vec<GameCompletion> = Vec::new();
-    for d in achieve_iter {
-        vec.push(d.unwrap());
-    }
-    Ok(vec)
+    Ok(())
 }
 
-pub fn save_game_completion(app_id: &i32, complete: i8, last_played: i64, has_achievements: bool, perfect: bool) -> Result<()> {
-    // Connect to SQLite database (creates the file if it doesn't exist)
+pub fn delete_game_completion(app_id: &i32) -> Result<()> {
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
-    
-    // Add in the achievement
+
     conn.execute(
-        "INSERT INTO steam_game_completion (app_id, complete, last_played, has_achievements, perfect) VALUES (?1, ?2, ?3, ?4, ?5) ON CONFLICT(app_id) DO UPDATE SET complete=?6, last_played=?7, has_achievements=?8, perfect=?9",
-        params![app_id, complete, last_played, has_achievements, perfect, complete, last_played, has_achievements, perfect],
+        "DELETE FROM steam_game_completion_v_2 WHERE app_id = ?1",
+        params![app_id],
     )?;
 
     Ok(())
@@ -74,18 +130,25 @@ pub fn drop_table() -> Result<()> {
     let conn: Connection = db_manager::get_connection();
 
         conn.execute(
-        "DROP TABLE IF EXISTS steam_game_completion",
+        "DROP TABLE IF EXISTS steam_game_completion_v_2",
         [], // No parameters needed
     )?;
 
     Ok(())
 }
 
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
 fn create_table(conn: &Connection) -> Result<()> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS steam_game_completion (
+        "CREATE TABLE IF NOT EXISTS steam_game_completion_v_2 (
             app_id INTEGER PRIMARY KEY,
-            complete INTEGER NOT NULL,
+            total INTEGER NOT NULL,
+            achieved INTEGER NOT NULL,
+            excluded INTEGER NOT NULL,
             last_played INTEGER NOT NULL,
             has_achievements BOOL NOT NULL,
             perfect BOOL NOT NULL
@@ -94,4 +157,41 @@ fn create_table(conn: &Connection) -> Result<()> {
     )?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completion(total: i32, achieved: i32, excluded: i32) -> GameCompletion {
+        GameCompletion { app_id: 1, total, achieved, excluded, last_played: 0, has_achievements: true, perfect: false }
+    }
+
+    #[test]
+    fn raw_counts_excluded_achievements() {
+        let c = completion(50, 40, 10);
+        assert_eq!(c.percentage(CompletionFormula::Raw), 80);
+        assert_eq!(c.percentage(CompletionFormula::ExclusionAdjusted), 100);
+    }
+
+    #[test]
+    fn percentage_rounds_down() {
+        let c = completion(3, 2, 0);
+        assert_eq!(c.percentage(CompletionFormula::Raw), 66);
+        assert_eq!(c.percentage(CompletionFormula::ExclusionAdjusted), 66);
+    }
+
+    #[test]
+    fn everything_left_excluded_is_complete() {
+        let c = completion(5, 0, 5);
+        assert_eq!(c.percentage(CompletionFormula::ExclusionAdjusted), 100);
+        assert_eq!(c.percentage(CompletionFormula::Raw), 0);
+    }
+
+    #[test]
+    fn no_achievements_is_zero() {
+        assert_eq!(completion(0, 0, 0).percentage(CompletionFormula::ExclusionAdjusted), 0);
+        let without = GameCompletion { has_achievements: false, ..completion(10, 10, 0) };
+        assert_eq!(without.percentage(CompletionFormula::Raw), 0);
+    }
+}
@@ -0,0 +1,74 @@
+use rusqlite::{params, Connection, Result};
+use chrono::NaiveDate;
+
+use db_lib::db_manager;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The last day a game was picked by a random roll
+#[derive(Debug, Clone)]
+pub struct GamePick {
+    pub app_id: i32,
+    pub picked_on: NaiveDate,
+}
+
+pub fn get_game_picks() -> Result<Vec<GamePick>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, picked_on FROM game_picks")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(GamePick {
+            app_id: row.get(0)?,
+            picked_on: NaiveDate::parse_from_str(&row.get::<_, String>(1)?, DATE_FORMAT).unwrap_or_default(),
+        })
+    })?;
+
+    let mut vec : Vec<GamePick> = Vec::new();
+    for p in iter {
+        vec.push(p?);
+    }
+    Ok(vec)
+}
+
+/// Picking a game again moves its pick to the new day
+pub fn save_game_pick(app_id: &i32, picked_on: &NaiveDate) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO game_picks (app_id, picked_on) VALUES (?1, ?2) ON CONFLICT(app_id) DO UPDATE SET picked_on=?2",
+        params![app_id, picked_on.format(DATE_FORMAT).to_string()],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_game_picks() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM game_picks",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS game_picks (
+            app_id INTEGER PRIMARY KEY,
+            picked_on TEXT NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
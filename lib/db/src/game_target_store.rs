@@ -75,6 +75,35 @@ pub fn save_game_target(app_id: &i32, complete: &bool) -> Result<()> {
     Ok(())
 }
 
+pub fn delete_game_target(app_id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM game_targets WHERE app_id = ?1",
+        params![app_id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_game_targets() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM game_targets",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
 fn create_table(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS game_targets (
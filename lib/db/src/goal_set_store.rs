@@ -0,0 +1,247 @@
+use rusqlite::{params, Connection, Result};
+use chrono::NaiveDate;
+use std::{fmt, str::FromStr};
+
+use db_lib::db_manager;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GoalSetKind {
+    Bingo,
+    MultiRoll,
+}
+
+impl GoalSetKind {
+    pub const ALL: [GoalSetKind; 2] = [
+        GoalSetKind::Bingo,
+        GoalSetKind::MultiRoll,
+    ];
+
+    // The value saved in the database
+    fn as_str(&self) -> &'static str {
+        match self {
+            GoalSetKind::Bingo => "bingo",
+            GoalSetKind::MultiRoll => "multi_roll",
+        }
+    }
+}
+
+impl fmt::Display for GoalSetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoalSetKind::Bingo => write!(f, "Bingo"),
+            GoalSetKind::MultiRoll => write!(f, "Multi roll"),
+        }
+    }
+}
+
+impl FromStr for GoalSetKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lowercase = s.trim().to_lowercase();
+        GoalSetKind::ALL.iter()
+            .find(|k| k.as_str() == lowercase)
+            .copied()
+            .ok_or(format!("Unknown goal set {s}, use bingo or multi_roll"))
+    }
+}
+
+/// A group of goals rolled together, such as a bingo card or a session of goals from several games
+#[derive(Debug, Clone)]
+pub struct GoalSet {
+    pub id: i32,
+    pub name: String,
+    pub kind: GoalSetKind,
+    pub size: i32, // The width of a bingo card, or the number of goals rolled together
+    pub seed: Option<i64>,
+    pub created_on: NaiveDate,
+}
+
+#[derive(Debug, Clone)]
+pub struct GoalSetItem {
+    pub position: i32, // Squares are numbered along each row
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub icon: String,
+    pub icon_gray: String,
+    pub rarity: Option<f64>, // Percent of players with the achievement, when known
+    pub completed: bool,
+}
+
+/// Every goal set, newest first
+pub fn get_goal_sets() -> Result<Vec<GoalSet>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, name, kind, size, seed, created_on FROM goal_sets ORDER BY id DESC")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(GoalSet {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            kind: row.get::<_, String>(2)?.parse().unwrap_or(GoalSetKind::Bingo),
+            size: row.get(3)?,
+            seed: row.get(4)?,
+            created_on: NaiveDate::parse_from_str(&row.get::<_, String>(5)?, DATE_FORMAT).unwrap_or_default(),
+        })
+    })?;
+
+    let mut vec : Vec<GoalSet> = Vec::new();
+    for s in iter {
+        vec.push(s?);
+    }
+    Ok(vec)
+}
+
+pub fn get_goal_set_items(set_id: &i32) -> Result<Vec<GoalSetItem>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT position, app_id, achievement_name, display_name, description, icon, icon_gray, rarity, completed
+            FROM goal_set_items WHERE set_id = ?1 ORDER BY position"
+    )?;
+    let iter = stmt.query_map([set_id], |row| {
+        Ok(GoalSetItem {
+            position: row.get(0)?,
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            display_name: row.get(3)?,
+            description: row.get(4)?,
+            icon: row.get(5)?,
+            icon_gray: row.get(6)?,
+            rarity: row.get(7)?,
+            completed: row.get(8)?,
+        })
+    })?;
+
+    let mut vec : Vec<GoalSetItem> = Vec::new();
+    for i in iter {
+        vec.push(i?);
+    }
+    Ok(vec)
+}
+
+/// The achievements still to do across every set, as (app_id, achievement_name)
+pub fn get_unfinished_items() -> Result<Vec<(i32, String)>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT DISTINCT app_id, achievement_name FROM goal_set_items WHERE completed = 0")?;
+    let iter = stmt.query_map([], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+
+    let mut vec : Vec<(i32, String)> = Vec::new();
+    for i in iter {
+        vec.push(i?);
+    }
+    Ok(vec)
+}
+
+/// Save a goal set and all of its items together, returns the id of the new set
+pub fn save_goal_set(name: &str, kind: &GoalSetKind, size: &i32, seed: &Option<i64>, created_on: &NaiveDate, items: &[GoalSetItem]) -> Result<i32> {
+    let mut conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO goal_sets (name, kind, size, seed, created_on) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![name, kind.as_str(), size, seed, created_on.format(DATE_FORMAT).to_string()],
+    )?;
+    let set_id = tx.last_insert_rowid() as i32;
+    for i in items {
+        tx.execute(
+            "INSERT INTO goal_set_items (set_id, position, app_id, achievement_name, display_name, description, icon, icon_gray, rarity, completed)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![set_id, i.position, i.app_id, i.achievement_name, i.display_name, i.description, i.icon, i.icon_gray, i.rarity, i.completed],
+        )?;
+    }
+    tx.commit()?;
+    Ok(set_id)
+}
+
+/// Mark the achievement as done in every set it is part of
+pub fn complete_item(app_id: &i32, achievement_name: &str) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "UPDATE goal_set_items SET completed = 1 WHERE app_id = ?1 AND achievement_name = ?2",
+        params![app_id, achievement_name],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_goal_set(id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM goal_set_items WHERE set_id = ?1",
+        params![id],
+    )?;
+    conn.execute(
+        "DELETE FROM goal_sets WHERE id = ?1",
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_goal_sets() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM goal_set_items",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "DELETE FROM goal_sets",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS goal_sets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            size INTEGER NOT NULL,
+            seed INTEGER,
+            created_on TEXT NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS goal_set_items (
+            set_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            description TEXT,
+            icon TEXT NOT NULL,
+            icon_gray TEXT NOT NULL,
+            rarity REAL,
+            completed INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (set_id, position)
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use rusqlite::{params, Connection, Result};
+
+use db_lib::db_manager;
+
+/// How long a game takes to beat, imported from an outside source
+#[derive(Debug, Clone)]
+pub struct HoursToBeat {
+    pub app_id: i32,
+    pub hours: f64,
+}
+
+pub fn get_hours_to_beat() -> Result<Vec<HoursToBeat>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id, hours FROM hours_to_beat")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(HoursToBeat {
+            app_id: row.get(0)?,
+            hours: row.get(1)?,
+        })
+    })?;
+
+    let mut vec : Vec<HoursToBeat> = Vec::new();
+    for h in iter {
+        vec.push(h?);
+    }
+    Ok(vec)
+}
+
+/// Importing a game again replaces its hours
+pub fn save_hours_to_beat(app_id: &i32, hours: &f64) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO hours_to_beat (app_id, hours) VALUES (?1, ?2) ON CONFLICT(app_id) DO UPDATE SET hours=?2",
+        params![app_id, hours],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_hours_to_beat() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM hours_to_beat",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS hours_to_beat (
+            app_id INTEGER PRIMARY KEY,
+            hours REAL NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
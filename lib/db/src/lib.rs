@@ -3,4 +3,19 @@ pub mod achievement_store;
 pub mod excluded_achievement_store;
 pub mod request_store;
 pub mod game_completion_cache;
-pub mod game_target_store;
\ No newline at end of file
+pub mod game_target_store;
+pub mod collection_store;
+pub mod note_store;
+pub mod settings_store;
+pub mod skipped_achievement_store;
+pub mod progress_snapshot_store;
+pub mod achievement_schema_store;
+pub mod refresh_checkpoint_store;
+pub mod schema_change_store;
+pub mod daily_challenge_store;
+pub mod achievement_rarity_store;
+pub mod goal_set_store;
+pub mod game_pick_store;
+pub mod exclusion_rule_store;
+pub mod achievement_unlock_store;
+pub mod hours_to_beat_store;
\ No newline at end of file
@@ -0,0 +1,148 @@
+use rusqlite::{params, Connection, Result, TransactionBehavior};
+
+use db_lib::db_manager;
+
+#[derive(Debug, Clone)]
+pub struct Note {
+    pub id: i32,
+    pub app_id: i32,
+    pub achievement_name: Option<String>, // None for a note on the whole game
+    pub note: String,
+}
+
+pub fn get_notes() -> Result<Vec<Note>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, app_id, achievement_name, note FROM notes ORDER BY app_id, achievement_name")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(Note {
+            id: row.get(0)?,
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            note: row.get(3)?,
+        })
+    })?;
+
+    let mut vec : Vec<Note> = Vec::new();
+    for n in iter {
+        vec.push(n?);
+    }
+    Ok(vec)
+}
+
+pub fn get_notes_for_app(app_id: &i32) -> Result<Vec<Note>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, app_id, achievement_name, note FROM notes WHERE app_id = ?1")?;
+    let iter = stmt.query_map([app_id], |row| {
+        Ok(Note {
+            id: row.get(0)?,
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            note: row.get(3)?,
+        })
+    })?;
+
+    let mut vec : Vec<Note> = Vec::new();
+    for n in iter {
+        vec.push(n?);
+    }
+    Ok(vec)
+}
+
+/// Case insensitive search of the note text
+pub fn search_notes(query: &str) -> Result<Vec<Note>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    // % and _ in the query are matched as they are rather than as wildcards
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let mut stmt = conn.prepare("SELECT id, app_id, achievement_name, note FROM notes WHERE note LIKE ?1 ESCAPE '\\' ORDER BY app_id, achievement_name")?;
+    let iter = stmt.query_map([format!("%{escaped}%")], |row| {
+        Ok(Note {
+            id: row.get(0)?,
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            note: row.get(3)?,
+        })
+    })?;
+
+    let mut vec : Vec<Note> = Vec::new();
+    for n in iter {
+        vec.push(n?);
+    }
+    Ok(vec)
+}
+
+/// Sets the note for the game or achievement, an empty note removes it
+pub fn save_note(app_id: &i32, achievement_name: &Option<String>, note: &str) -> Result<()> {
+    let mut conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    if note.trim().is_empty() {
+        conn.execute(
+            "DELETE FROM notes WHERE app_id = ?1 AND achievement_name IS ?2",
+            params![app_id, achievement_name],
+        )?;
+        return Ok(());
+    }
+
+    // There is no unique constraint to upsert on, the immediate transaction stops two saves both inserting
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let updated = tx.execute(
+        "UPDATE notes SET note = ?3 WHERE app_id = ?1 AND achievement_name IS ?2",
+        params![app_id, achievement_name, note],
+    )?;
+    if updated == 0 {
+        tx.execute(
+            "INSERT INTO notes (app_id, achievement_name, note) VALUES (?1, ?2, ?3)",
+            params![app_id, achievement_name, note],
+        )?;
+    }
+    tx.commit()
+}
+
+pub fn delete_note(id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM notes WHERE id = ?1",
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_notes() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM notes",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT,
+            note TEXT NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
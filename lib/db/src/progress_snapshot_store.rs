@@ -0,0 +1,94 @@
+use rusqlite::{params, Connection, Result};
+use chrono::NaiveDate;
+
+use db_lib::db_manager;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone)]
+pub struct ProgressSnapshot {
+    pub date: NaiveDate,
+    pub achievements_unlocked: i32,
+    pub games_completed: i32,
+    pub games_perfected: i32,
+    pub average_completion: f64, // Across games with achievements that have been played
+    pub request_count: i32,
+}
+
+/// Every snapshot, oldest first
+pub fn get_progress_snapshots() -> Result<Vec<ProgressSnapshot>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT date, achievements_unlocked, games_completed, games_perfected, average_completion, request_count FROM progress_snapshots ORDER BY date")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(ProgressSnapshot {
+            date: NaiveDate::parse_from_str(&row.get::<_, String>(0)?, DATE_FORMAT).unwrap_or_default(),
+            achievements_unlocked: row.get(1)?,
+            games_completed: row.get(2)?,
+            games_perfected: row.get(3)?,
+            average_completion: row.get(4)?,
+            request_count: row.get(5)?,
+        })
+    })?;
+
+    let mut vec : Vec<ProgressSnapshot> = Vec::new();
+    for s in iter {
+        vec.push(s?);
+    }
+    Ok(vec)
+}
+
+/// Only one snapshot is kept per day, saving again replaces it
+pub fn save_progress_snapshot(snapshot: &ProgressSnapshot) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO progress_snapshots (date, achievements_unlocked, games_completed, games_perfected, average_completion, request_count) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            ON CONFLICT(date) DO UPDATE SET achievements_unlocked=?2, games_completed=?3, games_perfected=?4, average_completion=?5, request_count=?6",
+        params![
+            snapshot.date.format(DATE_FORMAT).to_string(),
+            snapshot.achievements_unlocked,
+            snapshot.games_completed,
+            snapshot.games_perfected,
+            snapshot.average_completion,
+            snapshot.request_count,
+        ],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_progress_snapshots() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM progress_snapshots",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS progress_snapshots (
+            date TEXT PRIMARY KEY,
+            achievements_unlocked INTEGER NOT NULL,
+            games_completed INTEGER NOT NULL,
+            games_perfected INTEGER NOT NULL,
+            average_completion REAL NOT NULL,
+            request_count INTEGER NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
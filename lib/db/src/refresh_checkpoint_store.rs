@@ -0,0 +1,61 @@
+use rusqlite::{params, Connection, Result};
+
+use db_lib::db_manager;
+
+/// Games still to be fetched by an unfinished completion refresh
+pub fn get_pending() -> Result<Vec<i32>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id FROM refresh_checkpoint")?;
+    let iter = stmt.query_map([], |row| row.get(0))?;
+
+    let mut vec : Vec<i32> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+pub fn save_pending(app_ids: &[i32]) -> Result<()> {
+    let mut conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let tx = conn.transaction()?;
+    for app_id in app_ids {
+        tx.execute(
+            "INSERT INTO refresh_checkpoint (app_id) VALUES (?1) ON CONFLICT(app_id) DO NOTHING",
+            params![app_id],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Mark a game as fetched
+pub fn complete(app_id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM refresh_checkpoint WHERE app_id = ?1",
+        params![app_id],
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS refresh_checkpoint (
+            app_id INTEGER PRIMARY KEY
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
@@ -1,103 +1,196 @@
 use rusqlite::{params, Connection, Result};
-use chrono::{Local};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeDelta, TimeZone, Utc};
+use std::{fmt, str::FromStr};
 
 use db_lib::db_manager;
+use crate::settings_store;
 
-struct RequestCount {
-    date: String,
-    count: i32,
-}
+const QUOTA_SETTING: &str = "request_quota";
+const TIMEZONE_SETTING: &str = "request_timezone";
+// Requests are dropped from the log after this long, only today counts towards the quota
+const LOG_RETENTION_DAYS: i64 = 30;
+// Steam allows 100,000 calls a day, stay well under it by default
+pub const DEFAULT_QUOTA: i32 = 10000;
 
-pub fn increment() -> Result<bool> {
-    // Connect to SQLite database (creates the file if it doesn't exist)
-    let conn: Connection = db_manager::get_connection();
-    create_table(&conn)?;
-    
-    let mut stmt = conn.prepare("SELECT date, count FROM steam_request_count")?;
-    let mut result = stmt.query_map([], |row| {
-        Ok(RequestCount {
-            date: row.get(0)?,
-            count: row.get(1)?,
-        })
-    })?;
+/// The timezone used to decide when the daily quota resets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuotaTimezone {
+    #[default]
+    Local,
+    Utc,
+    Offset(FixedOffset),
+}
 
-    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
-    let count = if let Some(row) = result.next() {
-        if let Ok(current_count) = row {
-            let today_count = if current_count.date == today {
-                current_count.count + 1
-            }
-            else {
-                1
-            };
-            // Clear the table and then add in the latest value
-            conn.execute(
-                "DELETE FROM steam_request_count",
-                [], // No parameters needed
-            )?;
-            conn.execute(
-                "INSERT INTO steam_request_count (date, count) VALUES (?1,?2)",
-                params![today, today_count],
-            )?;
-            Ok(today_count)
+impl QuotaTimezone {
+    pub fn today(&self) -> NaiveDate {
+        match self {
+            QuotaTimezone::Local => Local::now().date_naive(),
+            QuotaTimezone::Utc => Utc::now().date_naive(),
+            QuotaTimezone::Offset(offset) => Utc::now().with_timezone(offset).date_naive(),
         }
-        else {
-            Err(row.err().unwrap())
+    }
+
+    /// Midnight at the start of the day in this timezone, in milliseconds since the epoch
+    fn start_of_day_millis(&self, day: NaiveDate) -> i64 {
+        let midnight = day.and_hms_opt(0, 0, 0).expect("Midnight is always valid");
+        match self {
+            QuotaTimezone::Local => Local.from_local_datetime(&midnight).earliest()
+                .map(|d| d.timestamp_millis())
+                .unwrap_or(midnight.and_utc().timestamp_millis()),
+            QuotaTimezone::Utc => midnight.and_utc().timestamp_millis(),
+            QuotaTimezone::Offset(offset) => offset.from_local_datetime(&midnight).single()
+                .map(|d: DateTime<FixedOffset>| d.timestamp_millis())
+                .unwrap_or(midnight.and_utc().timestamp_millis()),
         }
     }
-    else {
-        conn.execute(
-            "INSERT INTO steam_request_count (date, count) VALUES (?1,?2)",
-            params![today, 1],
-        )?;
-        Ok(1)
-    };
-    
-    // Check that the value is less than or equal to the set limit of 10000
-    if let Ok(c) = count {
-        Ok(c <= 10000)
+}
+
+impl fmt::Display for QuotaTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaTimezone::Local => write!(f, "local"),
+            QuotaTimezone::Utc => write!(f, "utc"),
+            QuotaTimezone::Offset(offset) => write!(f, "{offset}"),
+        }
     }
-    else {
-        Err(count.err().unwrap())
+}
+
+impl FromStr for QuotaTimezone {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "local" => Ok(QuotaTimezone::Local),
+            "utc" => Ok(QuotaTimezone::Utc),
+            other => other.parse::<FixedOffset>()
+                .map(QuotaTimezone::Offset)
+                .map_err(|_| format!("Unknown timezone {s}, use one of local, utc or an offset like +10:00")),
+        }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct EndpointUsage {
+    pub endpoint: String,
+    pub count: i32,
+    pub failures: i32, // Requests that got no response or a non success status
+    pub average_latency_ms: f64,
+}
+
+pub fn get_quota() -> Result<i32> {
+    Ok(settings_store::get_setting(QUOTA_SETTING)?
+        .and_then(|q| q.parse().ok())
+        .unwrap_or(DEFAULT_QUOTA))
+}
+
+pub fn save_quota(quota: &i32) -> Result<()> {
+    settings_store::save_setting(QUOTA_SETTING, &quota.to_string())
+}
+
+pub fn get_timezone() -> Result<QuotaTimezone> {
+    Ok(settings_store::get_setting(TIMEZONE_SETTING)?
+        .and_then(|t| t.parse().ok())
+        .unwrap_or_default())
+}
+
+pub fn save_timezone(timezone: &QuotaTimezone) -> Result<()> {
+    settings_store::save_setting(TIMEZONE_SETTING, &timezone.to_string())
+}
+
+/// Check there are requests left in today's quota, run before every request
+pub fn has_budget() -> Result<bool> {
+    Ok(get_count()? < get_quota()?)
+}
+
+/// Requests left in today's quota
+pub fn get_remaining() -> Result<i32> {
+    Ok((get_quota()? - get_count()?).max(0))
+}
+
+/// Record a request to the Steam API, status is None when no response was received
+pub fn log_request(endpoint: &str, app_id: &Option<i32>, status: &Option<u16>, latency_ms: &i64) -> Result<()> {
+    // Connect to SQLite database (creates the file if it doesn't exist)
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let now = Utc::now();
+    conn.execute(
+        "INSERT INTO steam_request_log (requested_at, endpoint, app_id, status, latency_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![now.timestamp_millis(), endpoint, app_id, status, latency_ms],
+    )?;
+    // Keep the log from growing without limit
+    conn.execute(
+        "DELETE FROM steam_request_log WHERE requested_at < ?1",
+        params![(now - TimeDelta::days(LOG_RETENTION_DAYS)).timestamp_millis()],
+    )?;
+
+    Ok(())
+}
+
+/// The number of requests made today in the quota timezone
 pub fn get_count() -> Result<i32> {
     // Connect to SQLite database (creates the file if it doesn't exist)
     let conn: Connection = db_manager::get_connection();
     create_table(&conn)?;
 
-    let today = Local::now().date_naive().format("%Y-%m-%d").to_string();
-    let mut stmt = conn.prepare("SELECT date, count FROM steam_request_count WHERE date = ?1")?;
-    let mut result = stmt.query_map([today], |row| {
-        Ok(RequestCount {
-            date: row.get(0)?,
+    conn.query_row(
+        "SELECT COUNT(*) FROM steam_request_log WHERE requested_at >= ?1",
+        params![start_of_today()?],
+        |row| row.get(0),
+    )
+}
+
+/// Today's requests grouped by endpoint, the busiest first
+pub fn get_usage_by_endpoint() -> Result<Vec<EndpointUsage>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT endpoint, COUNT(*), SUM(CASE WHEN status IS NULL OR status >= 400 THEN 1 ELSE 0 END), AVG(latency_ms)
+            FROM steam_request_log WHERE requested_at >= ?1 GROUP BY endpoint ORDER BY COUNT(*) DESC"
+    )?;
+    let iter = stmt.query_map([start_of_today()?], |row| {
+        Ok(EndpointUsage {
+            endpoint: row.get(0)?,
             count: row.get(1)?,
+            failures: row.get(2)?,
+            average_latency_ms: row.get(3)?,
         })
     })?;
 
-    if let Some(row) = result.next() {
-        if let Ok(r) = row {
-            Ok(r.count)
-        }
-        else {
-            Err(row.err().unwrap())
-        }
-    }
-    else {
-        Ok(0)
+    let mut vec : Vec<EndpointUsage> = Vec::new();
+    for u in iter {
+        vec.push(u?);
     }
+    Ok(vec)
+}
+
+fn start_of_today() -> Result<i64> {
+    let timezone = get_timezone()?;
+    Ok(timezone.start_of_day_millis(timezone.today()))
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
 }
 
 fn create_table(conn: &Connection) -> Result<()> {
     conn.execute(
-        "CREATE TABLE IF NOT EXISTS steam_request_count (
+        "CREATE TABLE IF NOT EXISTS steam_request_log (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
-            date TEXT NOT NULL,
-            count INTEGER NOT NULL
+            requested_at INTEGER NOT NULL,
+            endpoint TEXT NOT NULL,
+            app_id INTEGER,
+            status INTEGER,
+            latency_ms INTEGER NOT NULL
         )",
         [], // No parameters needed
     )?;
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS steam_request_log_requested_at ON steam_request_log (requested_at)",
+        [], // No parameters needed
+    )?;
 
     Ok(())
-}
\ No newline at end of file
+}
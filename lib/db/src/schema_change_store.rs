@@ -0,0 +1,136 @@
+use rusqlite::{params, Connection, Result};
+use chrono::NaiveDate;
+
+use db_lib::db_manager;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A game that gained achievements in an update after it was completed
+#[derive(Debug, Clone)]
+pub struct SchemaChange {
+    pub id: i32,
+    pub app_id: i32,
+    pub detected_on: NaiveDate,
+    pub previous_total: i32,
+    pub new_total: i32,
+}
+
+impl SchemaChange {
+    pub fn added(&self) -> i32 {
+        self.new_total - self.previous_total
+    }
+}
+
+/// Every change not yet dismissed, newest first
+pub fn get_schema_changes() -> Result<Vec<SchemaChange>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, app_id, detected_on, previous_total, new_total FROM schema_changes ORDER BY id DESC")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(SchemaChange {
+            id: row.get(0)?,
+            app_id: row.get(1)?,
+            detected_on: NaiveDate::parse_from_str(&row.get::<_, String>(2)?, DATE_FORMAT).unwrap_or_default(),
+            previous_total: row.get(3)?,
+            new_total: row.get(4)?,
+        })
+    })?;
+
+    let mut vec : Vec<SchemaChange> = Vec::new();
+    for c in iter {
+        vec.push(c?);
+    }
+    Ok(vec)
+}
+
+pub fn save_schema_change(app_id: &i32, detected_on: &NaiveDate, previous_total: &i32, new_total: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO schema_changes (app_id, detected_on, previous_total, new_total) VALUES (?1, ?2, ?3, ?4)",
+        params![app_id, detected_on.format(DATE_FORMAT).to_string(), previous_total, new_total],
+    )?;
+
+    Ok(())
+}
+
+/// Dismiss a change once it has been seen
+pub fn delete_schema_change(id: &i32) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM schema_changes WHERE id = ?1",
+        params![id],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_schema_changes() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM schema_changes",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+/// The apps whose schema was already checked on the day given
+pub fn get_checked_on(day: &NaiveDate) -> Result<Vec<i32>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT app_id FROM schema_checks WHERE checked_on = ?1")?;
+    let iter = stmt.query_map([day.format(DATE_FORMAT).to_string()], |row| row.get(0))?;
+
+    let mut vec : Vec<i32> = Vec::new();
+    for a in iter {
+        vec.push(a?);
+    }
+    Ok(vec)
+}
+
+pub fn save_checked(app_id: &i32, day: &NaiveDate) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO schema_checks (app_id, checked_on) VALUES (?1, ?2) ON CONFLICT(app_id) DO UPDATE SET checked_on=?2",
+        params![app_id, day.format(DATE_FORMAT).to_string()],
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_changes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            app_id INTEGER NOT NULL,
+            detected_on TEXT NOT NULL,
+            previous_total INTEGER NOT NULL,
+            new_total INTEGER NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_checks (
+            app_id INTEGER PRIMARY KEY,
+            checked_on TEXT NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
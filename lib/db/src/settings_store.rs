@@ -0,0 +1,90 @@
+use rusqlite::{params, Connection, Result};
+
+use db_lib::db_manager;
+
+#[derive(Debug, Clone)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+}
+
+pub fn get_settings() -> Result<Vec<Setting>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT key, value FROM settings ORDER BY key")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(Setting {
+            key: row.get(0)?,
+            value: row.get(1)?,
+        })
+    })?;
+
+    let mut vec : Vec<Setting> = Vec::new();
+    for s in iter {
+        vec.push(s?);
+    }
+    Ok(vec)
+}
+
+pub fn get_setting(key: &str) -> Result<Option<String>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?1")?;
+    let mut iter = stmt.query_map([key], |row| row.get(0))?;
+    iter.next().transpose()
+}
+
+pub fn save_setting(key: &str, value: &str) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value=?2",
+        params![key, value],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_setting(key: &str) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM settings WHERE key = ?1",
+        params![key],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_settings() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM settings",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
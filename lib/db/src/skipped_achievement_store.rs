@@ -0,0 +1,118 @@
+use rusqlite::{params, Connection, Result};
+use chrono::NaiveDate;
+
+use db_lib::db_manager;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone)]
+pub struct SkippedAchievement {
+    pub id: i32,
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub skipped_on: NaiveDate,
+    pub available_after: NaiveDate, // The achievement can be rolled again from this day
+}
+
+impl SkippedAchievement {
+    pub fn on_cooldown(&self, today: NaiveDate) -> bool {
+        self.available_after > today
+    }
+}
+
+/// Every skip, newest first
+pub fn get_skipped_achievements() -> Result<Vec<SkippedAchievement>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, app_id, achievement_name, display_name, skipped_on, available_after FROM skipped_achievements ORDER BY skipped_on DESC, id DESC")?;
+    let iter = stmt.query_map([], |row| {
+        Ok(SkippedAchievement {
+            id: row.get(0)?,
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            display_name: row.get(3)?,
+            skipped_on: parse_date(row.get(4)?),
+            available_after: parse_date(row.get(5)?),
+        })
+    })?;
+
+    let mut vec : Vec<SkippedAchievement> = Vec::new();
+    for s in iter {
+        vec.push(s?);
+    }
+    Ok(vec)
+}
+
+pub fn get_skipped_achievements_for_app(app_id: &i32) -> Result<Vec<SkippedAchievement>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT id, app_id, achievement_name, display_name, skipped_on, available_after FROM skipped_achievements WHERE app_id = ?1 ORDER BY skipped_on DESC, id DESC")?;
+    let iter = stmt.query_map([app_id], |row| {
+        Ok(SkippedAchievement {
+            id: row.get(0)?,
+            app_id: row.get(1)?,
+            achievement_name: row.get(2)?,
+            display_name: row.get(3)?,
+            skipped_on: parse_date(row.get(4)?),
+            available_after: parse_date(row.get(5)?),
+        })
+    })?;
+
+    let mut vec : Vec<SkippedAchievement> = Vec::new();
+    for s in iter {
+        vec.push(s?);
+    }
+    Ok(vec)
+}
+
+pub fn save_skipped_achievement(app_id: &i32, achievement_name: &str, display_name: &str, skipped_on: &NaiveDate, available_after: &NaiveDate) -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "INSERT INTO skipped_achievements (app_id, achievement_name, display_name, skipped_on, available_after) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![app_id, achievement_name, display_name, skipped_on.format(DATE_FORMAT).to_string(), available_after.format(DATE_FORMAT).to_string()],
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_all_skipped_achievements() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    conn.execute(
+        "DELETE FROM skipped_achievements",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
+
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
+fn parse_date(date: String) -> NaiveDate {
+    NaiveDate::parse_from_str(&date, DATE_FORMAT).unwrap_or_default()
+}
+
+fn create_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS skipped_achievements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            app_id INTEGER NOT NULL,
+            achievement_name TEXT NOT NULL,
+            display_name TEXT NOT NULL,
+            skipped_on TEXT NOT NULL,
+            available_after TEXT NOT NULL
+        )",
+        [], // No parameters needed
+    )?;
+
+    Ok(())
+}
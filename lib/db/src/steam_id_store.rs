@@ -25,6 +25,20 @@ pub fn get_id() -> Result<String> {
     Ok(id)
 }
 
+pub fn find_id() -> Result<Option<String>> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)?;
+
+    let mut stmt = conn.prepare("SELECT steam_id FROM steam_id_store")?;
+    let mut result = stmt.query_map([], |row| {
+        Ok(Id {
+            id: row.get(0)?
+        })
+    })?;
+
+    result.next().transpose().map(|id| id.map(|i| i.id))
+}
+
 pub fn save_id(id: &String) -> Result<()> {
     // Connect to SQLite database (creates the file if it doesn't exist)
     let conn: Connection = db_manager::get_connection();
@@ -45,6 +59,11 @@ pub fn save_id(id: &String) -> Result<()> {
     Ok(())
 }
 
+pub fn ensure_table() -> Result<()> {
+    let conn: Connection = db_manager::get_connection();
+    create_table(&conn)
+}
+
 fn create_table(conn: &Connection) -> Result<()> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS steam_id_store (
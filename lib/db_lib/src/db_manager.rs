@@ -1,17 +1,71 @@
-use rusqlite::{Connection};
+use rusqlite::{Connection, Result};
 use directories::{ProjectDirs};
-use std::fs;
+use std::{fs, io, path::PathBuf};
 
 static DATABASE_NAME: & str = "steam_randomiser_database.db";
+static RESTORE_POINT_NAME: & str = "steam_randomiser_database.db.restore";
 
-pub fn get_connection() -> Connection {
+fn data_dir() -> PathBuf {
     let binding = ProjectDirs::from("com", "everest", "steam_randomiser")
         .expect("Failed to get project directories");
     let data_dir =  binding.data_local_dir();
     if !fs::exists(data_dir).expect("Failed to check for directory") {
         fs::create_dir(data_dir).expect("Failed to create directory");
     }
-    let path = data_dir.join(DATABASE_NAME);
+    data_dir.to_path_buf()
+}
+
+pub fn get_connection() -> Connection {
+    let path = data_dir().join(DATABASE_NAME);
     let conn: Connection = Connection::open(path).expect("Failed to open a connection");
     conn
-}
\ No newline at end of file
+}
+
+/// Copy the database aside, so a change made over many connections can be undone with restore_restore_point.
+/// Store functions close their connection before returning, so the file is complete between calls
+pub fn create_restore_point() -> io::Result<()> {
+    let dir = data_dir();
+    fs::copy(dir.join(DATABASE_NAME), dir.join(RESTORE_POINT_NAME))?;
+    Ok(())
+}
+
+/// Put the database back as it was when the restore point was created
+pub fn restore_restore_point() -> io::Result<()> {
+    let dir = data_dir();
+    fs::copy(dir.join(RESTORE_POINT_NAME), dir.join(DATABASE_NAME))?;
+    Ok(())
+}
+
+pub fn remove_restore_point() -> io::Result<()> {
+    fs::remove_file(data_dir().join(RESTORE_POINT_NAME))
+}
+
+pub fn table_exists(table: &str) -> Result<bool> {
+    let conn: Connection = get_connection();
+    let count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+pub fn column_exists(table: &str, column: &str) -> Result<bool> {
+    let conn: Connection = get_connection();
+    let count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        [table, column],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+pub fn index_exists(index: &str) -> Result<bool> {
+    let conn: Connection = get_connection();
+    let count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'index' AND name = ?1",
+        [index],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
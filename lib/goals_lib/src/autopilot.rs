@@ -0,0 +1,99 @@
+use api::game_fetch::Game;
+use db::{achievement_store, achievement_store::Achievement, game_target_store};
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use crate::{goals, roll, roll::Roller, settings};
+
+/// How a goal is replaced when it is completed, keeping the number of goals steady
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Autopilot {
+    #[default]
+    Off,
+    /// Roll another achievement from the same game, falling back to the targets when the game has nothing left
+    SameGame,
+    /// Roll an achievement from one of the games targeted and not marked as complete
+    Targets,
+}
+
+impl Autopilot {
+    pub const ALL: [Autopilot; 3] = [
+        Autopilot::Off,
+        Autopilot::SameGame,
+        Autopilot::Targets,
+    ];
+
+    // The value saved in the settings
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Autopilot::Off => "off",
+            Autopilot::SameGame => "same_game",
+            Autopilot::Targets => "targets",
+        }
+    }
+}
+
+impl fmt::Display for Autopilot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Autopilot::Off => write!(f, "Off"),
+            Autopilot::SameGame => write!(f, "Same game"),
+            Autopilot::Targets => write!(f, "Targets"),
+        }
+    }
+}
+
+impl FromStr for Autopilot {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let lowercase = s.trim().to_lowercase();
+        Autopilot::ALL.iter()
+            .find(|a| a.as_str() == lowercase)
+            .copied()
+            .ok_or(format!("Unknown autopilot {s}, use one of off, same_game or targets"))
+    }
+}
+
+/// Roll a replacement for each completed goal, a game is passed over when it would go over its goal limit.
+/// Stops at the global goal limit, or when Steam can't be reached
+pub async fn refill(key : &str, steam_id : &str, completed: &[Achievement], owned_games: &HashMap<i32, Game>) {
+    let autopilot = settings::get_autopilot();
+    if autopilot == Autopilot::Off {
+        return;
+    }
+    let mut roller = Roller::new(None);
+    let mut cooldown = roll::Cooldown::load();
+    for c in completed {
+        let same_game = if autopilot == Autopilot::SameGame { owned_games.get(&c.app_id).cloned() } else { None };
+        let mut candidate_games: Vec<Game> = game_target_store::get_game_targets().expect("Failed to load targets")
+            .into_iter()
+            .filter(|t| !t.complete)
+            .filter_map(|t| owned_games.get(&t.app_id).cloned())
+            .collect();
+        let mut next_game = same_game;
+        let rolled = loop {
+            let Some(game) = next_game.take().or_else(|| roll::take_random_game(&mut candidate_games, &cooldown, roller.rng())) else {
+                break None;
+            };
+            candidate_games.retain(|g| g.appid != game.appid);
+            // Nothing else can be added once the goal limit is reached
+            if goals::check_goal_limit(1).is_err() {
+                return;
+            }
+            if goals::check_game_goal_limit(&game.appid).is_err() {
+                continue;
+            }
+            match goals::try_get_random_achievement_for_game(key, steam_id, &game, roller.rng()).await {
+                Ok(Some(a)) => break Some((game, a)),
+                Ok(None) => continue,
+                // Out of requests or offline, the rest are not replaced rather than failing the sync
+                Err(_) => return,
+            }
+        };
+        if let Some((game, a)) = rolled {
+            achievement_store::save_achievement(&a.name, &a.display_name, &a.description, &game.appid, &game.last_played, &Some(roller.stored_seed())).expect("Failed to save achievement");
+            cooldown.record(&game);
+        }
+    }
+}
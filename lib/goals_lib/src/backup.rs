@@ -0,0 +1,618 @@
+use db::{achievement_store, collection_store, daily_challenge_store, daily_challenge_store::DailyChallenge, goal_set_store, goal_set_store::{GoalSetItem, GoalSetKind}, excluded_achievement_store, exclusion_rule_store, exclusion_rule_store::RuleKind, game_completion_cache, game_target_store, hours_to_beat_store, note_store, progress_snapshot_store, progress_snapshot_store::ProgressSnapshot, settings_store, skipped_achievement_store, steam_id_store};
+use db_lib::db_manager;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashSet, fs, str::FromStr};
+
+use crate::exclusion_rules;
+
+// Increase this when a change to the document can't be read by older versions
+pub const BACKUP_VERSION: u32 = 1;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Keep existing data and add anything missing from the backup
+    #[default]
+    Merge,
+    /// Remove existing data before loading the backup
+    Replace,
+}
+
+impl FromStr for ImportMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "merge" => Ok(ImportMode::Merge),
+            "replace" => Ok(ImportMode::Replace),
+            _ => Err(format!("Unknown import mode {s}, use one of merge or replace")),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Backup {
+    pub version: u32,
+    pub steam_id: Option<String>,
+    #[serde(default)]
+    pub goals: Vec<BackupGoal>,
+    #[serde(default)]
+    pub exclusions: Vec<BackupExclusion>,
+    #[serde(default)]
+    pub targets: Vec<BackupTarget>,
+    #[serde(default)]
+    pub collections: Vec<BackupCollection>,
+    #[serde(default)]
+    pub notes: Vec<BackupNote>,
+    #[serde(default)]
+    pub skips: Vec<BackupSkip>,
+    #[serde(default)]
+    pub settings: Vec<BackupSetting>,
+    #[serde(default)]
+    pub snapshots: Vec<BackupSnapshot>,
+    #[serde(default)]
+    pub daily_challenges: Vec<BackupDailyChallenge>,
+    #[serde(default)]
+    pub goal_sets: Vec<BackupGoalSet>,
+    #[serde(default)]
+    pub exclusion_rules: Vec<BackupExclusionRule>,
+    #[serde(default)]
+    pub hours_to_beat: Vec<BackupHoursToBeat>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupGoal {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub last_played: i64,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub due_date: Option<String>,
+    #[serde(default)]
+    pub seed: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupExclusion {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupTarget {
+    pub app_id: i32,
+    pub complete: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupCollection {
+    pub name: String,
+    pub app_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupNote {
+    pub app_id: i32,
+    pub achievement_name: Option<String>,
+    pub note: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupSkip {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub skipped_on: String,
+    pub available_after: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupSetting {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupSnapshot {
+    pub date: String,
+    pub achievements_unlocked: i32,
+    pub games_completed: i32,
+    pub games_perfected: i32,
+    pub average_completion: f64,
+    pub request_count: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupDailyChallenge {
+    pub date: String,
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub completed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupGoalSet {
+    pub name: String,
+    pub kind: String,
+    pub size: i32,
+    pub seed: Option<i64>,
+    pub created_on: String,
+    pub items: Vec<BackupGoalSetItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupGoalSetItem {
+    pub position: i32,
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub icon: String,
+    pub icon_gray: String,
+    pub rarity: Option<f64>,
+    pub completed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupExclusionRule {
+    pub kind: String,
+    pub value: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupHoursToBeat {
+    pub app_id: i32,
+    pub hours: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub goals: usize,
+    pub exclusions: usize,
+    pub targets: usize,
+    pub collections: usize,
+    pub notes: usize,
+    pub skips: usize,
+    pub settings: usize,
+    pub snapshots: usize,
+    pub daily_challenges: usize,
+    pub goal_sets: usize,
+    pub exclusion_rules: usize,
+    pub hours_to_beat: usize,
+    pub steam_id: bool,
+}
+
+/// Collect all the user-authored data, caches are not included as they can be rebuilt
+pub fn create_backup() -> Backup {
+    Backup {
+        version: BACKUP_VERSION,
+        steam_id: steam_id_store::find_id().expect("Failed to load the steam id"),
+        goals: achievement_store::get_achievements().expect("Failed to load achievements")
+            .into_iter()
+            .map(|a| BackupGoal {
+                app_id: a.app_id,
+                achievement_name: a.achievement_name,
+                display_name: a.display_name,
+                description: a.description,
+                last_played: a.last_played,
+                priority: a.priority,
+                due_date: a.due_date.map(|d| d.format(DATE_FORMAT).to_string()),
+                seed: a.seed,
+            })
+            .collect(),
+        exclusions: excluded_achievement_store::get_excluded_achievements().expect("Failed to load exclusions")
+            .into_iter()
+            .map(|e| BackupExclusion {
+                app_id: e.app_id,
+                achievement_name: e.achievement_name,
+                reason: e.reason.map(|r| r.to_string()),
+            })
+            .collect(),
+        targets: game_target_store::get_game_targets().expect("Failed to load targets")
+            .into_iter()
+            .map(|t| BackupTarget {
+                app_id: t.app_id,
+                complete: t.complete,
+            })
+            .collect(),
+        collections: collection_store::get_collections().expect("Failed to load collections")
+            .into_iter()
+            .map(|c| BackupCollection {
+                app_ids: collection_store::get_app_ids_for_collection(&c.id).expect("Failed to load collection games"),
+                name: c.name,
+            })
+            .collect(),
+        notes: note_store::get_notes().expect("Failed to load notes")
+            .into_iter()
+            .map(|n| BackupNote {
+                app_id: n.app_id,
+                achievement_name: n.achievement_name,
+                note: n.note,
+            })
+            .collect(),
+        skips: skipped_achievement_store::get_skipped_achievements().expect("Failed to load skipped achievements")
+            .into_iter()
+            .map(|s| BackupSkip {
+                app_id: s.app_id,
+                achievement_name: s.achievement_name,
+                display_name: s.display_name,
+                skipped_on: s.skipped_on.format(DATE_FORMAT).to_string(),
+                available_after: s.available_after.format(DATE_FORMAT).to_string(),
+            })
+            .collect(),
+        settings: settings_store::get_settings().expect("Failed to load settings")
+            .into_iter()
+            .map(|s| BackupSetting {
+                key: s.key,
+                value: s.value,
+            })
+            .collect(),
+        snapshots: progress_snapshot_store::get_progress_snapshots().expect("Failed to load progress snapshots")
+            .into_iter()
+            .map(|s| BackupSnapshot {
+                date: s.date.format(DATE_FORMAT).to_string(),
+                achievements_unlocked: s.achievements_unlocked,
+                games_completed: s.games_completed,
+                games_perfected: s.games_perfected,
+                average_completion: s.average_completion,
+                request_count: s.request_count,
+            })
+            .collect(),
+        daily_challenges: daily_challenge_store::get_daily_challenges().expect("Failed to load daily challenges")
+            .into_iter()
+            .map(|c| BackupDailyChallenge {
+                date: c.date.format(DATE_FORMAT).to_string(),
+                app_id: c.app_id,
+                achievement_name: c.achievement_name,
+                display_name: c.display_name,
+                description: c.description,
+                completed: c.completed,
+            })
+            .collect(),
+        goal_sets: goal_set_store::get_goal_sets().expect("Failed to load goal sets")
+            .into_iter()
+            .map(|s| BackupGoalSet {
+                items: goal_set_store::get_goal_set_items(&s.id).expect("Failed to load goal set items")
+                    .into_iter()
+                    .map(|i| BackupGoalSetItem {
+                        position: i.position,
+                        app_id: i.app_id,
+                        achievement_name: i.achievement_name,
+                        display_name: i.display_name,
+                        description: i.description,
+                        icon: i.icon,
+                        icon_gray: i.icon_gray,
+                        rarity: i.rarity,
+                        completed: i.completed,
+                    })
+                    .collect(),
+                name: s.name,
+                kind: s.kind.to_string(),
+                size: s.size,
+                seed: s.seed,
+                created_on: s.created_on.format(DATE_FORMAT).to_string(),
+            })
+            .collect(),
+        exclusion_rules: exclusion_rule_store::get_exclusion_rules().expect("Failed to load exclusion rules")
+            .into_iter()
+            .map(|r| BackupExclusionRule {
+                kind: r.kind.to_string(),
+                value: r.value,
+                reason: r.reason.map(|r| r.to_string()),
+            })
+            .collect(),
+        hours_to_beat: hours_to_beat_store::get_hours_to_beat().expect("Failed to load hours to beat")
+            .into_iter()
+            .map(|h| BackupHoursToBeat {
+                app_id: h.app_id,
+                hours: h.hours,
+            })
+            .collect(),
+    }
+}
+
+pub fn export_to_file(path: &str) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&create_backup()).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())
+}
+
+pub fn import_from_file(path: &str, mode: ImportMode) -> Result<ImportSummary, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let backup: Backup = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    import_backup(backup, mode)
+}
+
+/// Load a backup into the database. Replace empties the tables before loading,
+/// the stores each use their own connection so instead of a transaction the database file is copied first
+/// and put back if anything fails part way
+pub fn import_backup(backup: Backup, mode: ImportMode) -> Result<ImportSummary, String> {
+    if mode == ImportMode::Merge {
+        return load_backup(backup, mode);
+    }
+    // Make sure the database exists to copy
+    steam_id_store::find_id().map_err(|e| e.to_string())?;
+    db_manager::create_restore_point().map_err(|e| e.to_string())?;
+    let result = load_backup(backup, mode);
+    if result.is_err() {
+        db_manager::restore_restore_point().map_err(|e| e.to_string())?;
+    }
+    db_manager::remove_restore_point().map_err(|e| e.to_string())?;
+    result
+}
+
+fn load_backup(backup: Backup, mode: ImportMode) -> Result<ImportSummary, String> {
+    if backup.version > BACKUP_VERSION {
+        return Err(format!("Backup version {} is newer than the supported version {}", backup.version, BACKUP_VERSION));
+    }
+    // Validate everything before touching the database so a bad file changes nothing
+    for g in &backup.goals {
+        if let Some(d) = &g.due_date {
+            if achievement_store::parse_due_date(Some(d.clone())).is_none() {
+                return Err(format!("Invalid due date {d} for the goal {}", g.achievement_name));
+            }
+        }
+    }
+    let mut skips = Vec::new();
+    for s in backup.skips {
+        let skipped_on = NaiveDate::parse_from_str(&s.skipped_on, DATE_FORMAT).map_err(|e| format!("Invalid skip date {}: {e}", s.skipped_on))?;
+        let available_after = NaiveDate::parse_from_str(&s.available_after, DATE_FORMAT).map_err(|e| format!("Invalid skip date {}: {e}", s.available_after))?;
+        skips.push((s, skipped_on, available_after));
+    }
+    let mut snapshots = Vec::new();
+    for s in backup.snapshots {
+        snapshots.push(ProgressSnapshot {
+            date: NaiveDate::parse_from_str(&s.date, DATE_FORMAT).map_err(|e| format!("Invalid snapshot date {}: {e}", s.date))?,
+            achievements_unlocked: s.achievements_unlocked,
+            games_completed: s.games_completed,
+            games_perfected: s.games_perfected,
+            average_completion: s.average_completion,
+            request_count: s.request_count,
+        });
+    }
+    let mut daily_challenges = Vec::new();
+    for c in backup.daily_challenges {
+        daily_challenges.push(DailyChallenge {
+            date: NaiveDate::parse_from_str(&c.date, DATE_FORMAT).map_err(|e| format!("Invalid daily challenge date {}: {e}", c.date))?,
+            app_id: c.app_id,
+            achievement_name: c.achievement_name,
+            display_name: c.display_name,
+            description: c.description,
+            completed: c.completed,
+        });
+    }
+    let mut goal_sets = Vec::new();
+    for s in backup.goal_sets {
+        let kind: GoalSetKind = s.kind.parse()?;
+        let created_on = NaiveDate::parse_from_str(&s.created_on, DATE_FORMAT).map_err(|e| format!("Invalid goal set date {}: {e}", s.created_on))?;
+        goal_sets.push((s, kind, created_on));
+    }
+    let mut exclusion_rules = Vec::new();
+    for r in backup.exclusion_rules {
+        let kind: RuleKind = r.kind.parse()?;
+        let value = exclusion_rules::validate(&kind, &Some(r.value))?;
+        let reason = r.reason.as_ref().map(|r| r.parse()).transpose()?;
+        exclusion_rules.push((kind, value, reason));
+    }
+    for h in &backup.hours_to_beat {
+        if !h.hours.is_finite() || h.hours <= 0.0 {
+            return Err(format!("Invalid hours to beat {} for the app {}", h.hours, h.app_id));
+        }
+    }
+    let mut exclusions = Vec::new();
+    for e in backup.exclusions {
+        let reason = e.reason.as_ref().map(|r| r.parse()).transpose()?;
+        exclusions.push((e, reason));
+    }
+
+    if mode == ImportMode::Replace {
+        achievement_store::delete_all_achievements().map_err(|e| e.to_string())?;
+        excluded_achievement_store::delete_all_excluded_achievements().map_err(|e| e.to_string())?;
+        game_target_store::delete_all_game_targets().map_err(|e| e.to_string())?;
+        collection_store::delete_all_collections().map_err(|e| e.to_string())?;
+        note_store::delete_all_notes().map_err(|e| e.to_string())?;
+        skipped_achievement_store::delete_all_skipped_achievements().map_err(|e| e.to_string())?;
+        settings_store::delete_all_settings().map_err(|e| e.to_string())?;
+        progress_snapshot_store::delete_all_progress_snapshots().map_err(|e| e.to_string())?;
+        daily_challenge_store::delete_all_daily_challenges().map_err(|e| e.to_string())?;
+        goal_set_store::delete_all_goal_sets().map_err(|e| e.to_string())?;
+        exclusion_rule_store::delete_all_exclusion_rules().map_err(|e| e.to_string())?;
+        hours_to_beat_store::delete_all_hours_to_beat().map_err(|e| e.to_string())?;
+    }
+
+    let mut summary = ImportSummary::default();
+    if let Some(id) = backup.steam_id {
+        let existing = steam_id_store::find_id().map_err(|e| e.to_string())?;
+        if mode == ImportMode::Replace || existing.is_none() {
+            steam_id_store::save_id(&id).map_err(|e| e.to_string())?;
+            summary.steam_id = true;
+        }
+    }
+
+    let existing_goals: HashSet<(i32, String)> = achievement_store::get_achievements().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|a| (a.app_id, a.achievement_name))
+        .collect();
+    for g in backup.goals {
+        if existing_goals.contains(&(g.app_id, g.achievement_name.clone())) {
+            continue;
+        }
+        achievement_store::save_achievement(&g.achievement_name, &g.display_name, &g.description, &g.app_id, &g.last_played, &g.seed).map_err(|e| e.to_string())?;
+        if g.priority != 0 || g.due_date.is_some() {
+            let saved = achievement_store::get_achievements_for_app(&g.app_id).map_err(|e| e.to_string())?
+                .into_iter()
+                .find(|a| a.achievement_name == g.achievement_name)
+                .ok_or(format!("Failed to find the imported goal {}", g.achievement_name))?;
+            achievement_store::update_priority(&saved.id, &g.priority).map_err(|e| e.to_string())?;
+            achievement_store::update_due_date(&saved.id, &achievement_store::parse_due_date(g.due_date)).map_err(|e| e.to_string())?;
+        }
+        summary.goals += 1;
+    }
+
+    let existing_exclusions: HashSet<(i32, String)> = excluded_achievement_store::get_excluded_achievements().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|e| (e.app_id, e.achievement_name))
+        .collect();
+    let mut changed_apps: HashSet<i32> = HashSet::new();
+    for (e, reason) in exclusions {
+        if existing_exclusions.contains(&(e.app_id, e.achievement_name.clone())) {
+            continue;
+        }
+        excluded_achievement_store::save_excluded_achievement(&e.achievement_name, &e.app_id, &reason).map_err(|e| e.to_string())?;
+        changed_apps.insert(e.app_id);
+        summary.exclusions += 1;
+    }
+
+    let existing_targets: HashSet<i32> = game_target_store::get_game_targets().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|t| t.app_id)
+        .collect();
+    for t in backup.targets {
+        if existing_targets.contains(&t.app_id) {
+            continue;
+        }
+        game_target_store::save_game_target(&t.app_id, &t.complete).map_err(|e| e.to_string())?;
+        summary.targets += 1;
+    }
+
+    // Collections are merged, games already in a collection are left as they are
+    for c in backup.collections {
+        let collection_id = collection_store::save_collection(&c.name).map_err(|e| e.to_string())?;
+        for app_id in c.app_ids {
+            collection_store::add_game_to_collection(&collection_id, &app_id).map_err(|e| e.to_string())?;
+        }
+        summary.collections += 1;
+    }
+
+    let existing_notes: HashSet<(i32, Option<String>)> = note_store::get_notes().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|n| (n.app_id, n.achievement_name))
+        .collect();
+    for n in backup.notes {
+        if existing_notes.contains(&(n.app_id, n.achievement_name.clone())) {
+            continue;
+        }
+        note_store::save_note(&n.app_id, &n.achievement_name, &n.note).map_err(|e| e.to_string())?;
+        summary.notes += 1;
+    }
+
+    // Skips are history, the same skip in both is only kept once
+    let existing_skips: HashSet<(i32, String, NaiveDate)> = skipped_achievement_store::get_skipped_achievements().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| (s.app_id, s.achievement_name, s.skipped_on))
+        .collect();
+    for (s, skipped_on, available_after) in skips {
+        if existing_skips.contains(&(s.app_id, s.achievement_name.clone(), skipped_on)) {
+            continue;
+        }
+        skipped_achievement_store::save_skipped_achievement(&s.app_id, &s.achievement_name, &s.display_name, &skipped_on, &available_after).map_err(|e| e.to_string())?;
+        summary.skips += 1;
+    }
+
+    let existing_settings: HashSet<String> = settings_store::get_settings().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| s.key)
+        .collect();
+    for s in backup.settings {
+        if existing_settings.contains(&s.key) {
+            continue;
+        }
+        settings_store::save_setting(&s.key, &s.value).map_err(|e| e.to_string())?;
+        summary.settings += 1;
+    }
+
+    let existing_snapshots: HashSet<NaiveDate> = progress_snapshot_store::get_progress_snapshots().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| s.date)
+        .collect();
+    for s in snapshots {
+        if existing_snapshots.contains(&s.date) {
+            continue;
+        }
+        progress_snapshot_store::save_progress_snapshot(&s).map_err(|e| e.to_string())?;
+        summary.snapshots += 1;
+    }
+
+    let existing_challenges: HashSet<NaiveDate> = daily_challenge_store::get_daily_challenges().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|c| c.date)
+        .collect();
+    for c in daily_challenges {
+        if existing_challenges.contains(&c.date) {
+            continue;
+        }
+        daily_challenge_store::save_daily_challenge(&c).map_err(|e| e.to_string())?;
+        summary.daily_challenges += 1;
+    }
+
+    let existing_sets: HashSet<(String, NaiveDate)> = goal_set_store::get_goal_sets().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|s| (s.name, s.created_on))
+        .collect();
+    for (s, kind, created_on) in goal_sets {
+        if existing_sets.contains(&(s.name.clone(), created_on)) {
+            continue;
+        }
+        let items: Vec<GoalSetItem> = s.items.into_iter()
+            .map(|i| GoalSetItem {
+                position: i.position,
+                app_id: i.app_id,
+                achievement_name: i.achievement_name,
+                display_name: i.display_name,
+                description: i.description,
+                icon: i.icon,
+                icon_gray: i.icon_gray,
+                rarity: i.rarity,
+                completed: i.completed,
+            })
+            .collect();
+        goal_set_store::save_goal_set(&s.name, &kind, &s.size, &s.seed, &created_on, &items).map_err(|e| e.to_string())?;
+        summary.goal_sets += 1;
+    }
+
+    // Saving a rule drops the cached completion of the games it matches
+    let existing_rules: HashSet<(RuleKind, String)> = exclusion_rule_store::get_exclusion_rules().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| (r.kind, r.value))
+        .collect();
+    for (kind, value, reason) in exclusion_rules {
+        if existing_rules.contains(&(kind, value.clone())) {
+            continue;
+        }
+        exclusion_rules::save_rule(&kind, &Some(value), &reason)?;
+        summary.exclusion_rules += 1;
+    }
+
+    let existing_hours: HashSet<i32> = hours_to_beat_store::get_hours_to_beat().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|h| h.app_id)
+        .collect();
+    for h in backup.hours_to_beat {
+        if existing_hours.contains(&h.app_id) {
+            continue;
+        }
+        hours_to_beat_store::save_hours_to_beat(&h.app_id, &h.hours).map_err(|e| e.to_string())?;
+        summary.hours_to_beat += 1;
+    }
+
+    // Exclusions change the completion counts, so drop what is cached for those games
+    if mode == ImportMode::Replace {
+        game_completion_cache::drop_table().map_err(|e| e.to_string())?;
+    }
+    else {
+        for app_id in changed_apps {
+            game_completion_cache::delete_game_completion(&app_id).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(summary)
+}
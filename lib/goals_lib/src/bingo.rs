@@ -0,0 +1,142 @@
+use api::game_fetch::Game;
+use db::{game_completion_cache, goal_set_store, goal_set_store::{GoalSet, GoalSetItem, GoalSetKind}};
+use chrono::Local;
+use rand::prelude::*;
+
+use std::collections::HashSet;
+
+use crate::{goal_set, goal_set::RarityRule, goals, roll, roll::Roller};
+
+/// The rarity bands a balanced card spreads its squares over, by the percent of players with the achievement
+pub const RARITY_BANDS: [(f64, f64); 4] = [
+    (50.0, 100.0), // Common
+    (20.0, 50.0), // Uncommon
+    (5.0, 20.0), // Rare
+    (0.0, 5.0), // Ultra rare
+];
+
+pub const MAX_SIZE: usize = 7;
+const CELL_WIDTH: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct BingoCard {
+    pub set: GoalSet,
+    pub items: Vec<GoalSetItem>,
+}
+
+impl BingoCard {
+    pub fn size(&self) -> usize {
+        self.set.size as usize
+    }
+
+    pub fn square(&self, row: usize, column: usize) -> Option<&GoalSetItem> {
+        self.items.iter().find(|i| i.position as usize == row * self.size() + column)
+    }
+
+    /// Rows, columns and both diagonals with every square completed
+    pub fn completed_lines(&self) -> usize {
+        let size = self.size();
+        let done = |row: usize, column: usize| self.square(row, column).is_some_and(|i| i.completed);
+        let rows = (0..size).filter(|r| (0..size).all(|c| done(*r, c))).count();
+        let columns = (0..size).filter(|c| (0..size).all(|r| done(r, *c))).count();
+        let diagonal = (0..size).all(|i| done(i, i)) as usize;
+        let anti_diagonal = (0..size).all(|i| done(i, size - 1 - i)) as usize;
+        rows + columns + diagonal + anti_diagonal
+    }
+
+    /// The card as a text grid, completed squares are marked with an X
+    pub fn render(&self, game_name: impl Fn(&i32) -> String) -> String {
+        let size = self.size();
+        let fit = |s: &str| {
+            let mut cell: String = s.chars().take(CELL_WIDTH).collect();
+            if s.chars().count() > CELL_WIDTH {
+                cell.pop();
+                cell.push('~');
+            }
+            format!("{cell:<CELL_WIDTH$}")
+        };
+        let border = format!("+{}\n", format!("{}+", "-".repeat(CELL_WIDTH + 2)).repeat(size));
+        let mut card = border.clone();
+        for row in 0..size {
+            let squares: Vec<Option<&GoalSetItem>> = (0..size).map(|column| self.square(row, column)).collect();
+            let lines: [Vec<String>; 2] = [
+                squares.iter().map(|s| s.map(|i| format!("{} {}", if i.completed { "X" } else { " " }, fit(&i.display_name))).unwrap_or(fit(""))).collect(),
+                squares.iter().map(|s| s.map(|i| format!("  {}", fit(&game_name(&i.app_id)))).unwrap_or(fit(""))).collect(),
+            ];
+            for line in lines {
+                card.push_str(&format!("|{}|\n", line.iter().map(|c| format!("{c:<width$}", width = CELL_WIDTH + 2)).collect::<Vec<String>>().join("|")));
+            }
+            card.push_str(&border);
+        }
+        card
+    }
+}
+
+pub fn get_card(set_id: &i32) -> Option<BingoCard> {
+    goal_set_store::get_goal_sets().expect("Failed to load goal sets")
+        .into_iter()
+        .find(|s| s.id == *set_id && s.kind == GoalSetKind::Bingo)
+        .map(|set| BingoCard {
+            items: goal_set_store::get_goal_set_items(&set.id).expect("Failed to load goal set items"),
+            set,
+        })
+}
+
+/// The most recently generated card
+pub fn get_latest_card() -> Option<BingoCard> {
+    goal_set_store::get_goal_sets().expect("Failed to load goal sets")
+        .into_iter()
+        .find(|s| s.kind == GoalSetKind::Bingo)
+        .map(|set| BingoCard {
+            items: goal_set_store::get_goal_set_items(&set.id).expect("Failed to load goal set items"),
+            set,
+        })
+}
+
+/// Generate a size by size card with every square from a different game, each square is also saved as a goal.
+/// Balancing spreads the squares evenly over the rarity bands, falling back to any rarity for games without one in their band
+pub async fn generate_card(key : &str, steam_id : &str, games: &[Game], size: usize, balance_rarity: bool, roller: &mut Roller) -> Result<BingoCard, String> {
+    if size == 0 || size > MAX_SIZE {
+        return Err(format!("A bingo card must be between 1 and {MAX_SIZE} squares wide"));
+    }
+    let squares = size * size;
+    goals::check_goal_limit(squares)?;
+    // Only games with achievements left can fill a square
+    let unfinished: HashSet<i32> = game_completion_cache::get_game_completion().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|c| c.has_achievements && c.achieved < c.total)
+        .map(|c| c.app_id)
+        .collect();
+    let mut candidate_games: Vec<Game> = games.iter()
+        .filter(|g| unfinished.contains(&g.appid) && goals::check_game_goal_limit(&g.appid).is_ok())
+        .cloned()
+        .collect();
+    if candidate_games.len() < squares {
+        return Err(format!("Only {} games have achievements left and room for a goal, a {size}x{size} card needs {squares}", candidate_games.len()));
+    }
+
+    // Spread the bands over the card so they are not in the same rows
+    let mut bands: Vec<usize> = (0..squares).map(|i| i % RARITY_BANDS.len()).collect();
+    bands.shuffle(roller.rng());
+
+    let today = Local::now().date_naive();
+    let cooldown = roll::Cooldown::for_roller(roller);
+    let mut items: Vec<GoalSetItem> = Vec::new();
+    let mut picked_games: Vec<Game> = Vec::new();
+    for (position, band) in bands.into_iter().enumerate() {
+        let item = loop {
+            let Some(game) = roll::take_random_game(&mut candidate_games, &cooldown, roller.rng()) else {
+                return Err(format!("Ran out of games with achievements to pick after {position} squares"));
+            };
+            let rarity = if balance_rarity { RarityRule::Prefer(RARITY_BANDS[band]) } else { RarityRule::Any };
+            if let Some(item) = goal_set::roll_item(key, steam_id, &game, position as i32, rarity, roller).await? {
+                picked_games.push(game);
+                break item;
+            }
+        };
+        items.push(item);
+    }
+
+    let set_id = goal_set::save_with_goals(&format!("Bingo {today}"), &GoalSetKind::Bingo, size as i32, &items, &picked_games, roller)?;
+    get_card(&set_id).ok_or("Failed to load the new card".to_string())
+}
@@ -0,0 +1,71 @@
+use db::{game_completion_cache, game_completion_cache::{CompletionFormula, GameCompletion}, game_target_store};
+
+use std::collections::HashMap;
+
+/// What counts as complete and perfect, applied when the cached completion is read so a change doesn't need a refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionPolicy {
+    /// Whether excluded achievements still count against the progress
+    pub formula: CompletionFormula,
+    /// The progress percent a game needs to count as complete
+    pub complete_threshold: i8,
+    /// Whether a target marked as complete is also perfect, games without achievements always are
+    pub targets_perfect: bool,
+}
+
+impl Default for CompletionPolicy {
+    fn default() -> Self {
+        CompletionPolicy {
+            formula: CompletionFormula::default(),
+            complete_threshold: 100,
+            targets_perfect: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameProgress {
+    pub completion: GameCompletion,
+    pub progress: i8,
+    pub complete: bool,
+    pub perfect: bool,
+}
+
+impl GameProgress {
+    pub fn display(&self) -> String {
+        if self.completion.has_achievements {
+            self.completion.counts_display()
+        }
+        else if self.complete {
+            "Complete".to_string()
+        }
+        else {
+            "-".to_string()
+        }
+    }
+}
+
+/// Combine the cached completion with the game targets using the policy, a target marked as complete always counts as 100
+pub fn get_game_progress(policy: CompletionPolicy) -> HashMap<i32, GameProgress> {
+    let completed_targets: Vec<i32> = game_target_store::get_game_targets().expect("Failed to load targets")
+        .iter()
+        .filter(|t| t.complete)
+        .map(|t| t.app_id)
+        .collect();
+    game_completion_cache::get_game_completion()
+        .expect("Failed to load completed games")
+        .into_iter()
+        .map(|c| {
+            let marked_complete = completed_targets.contains(&c.app_id);
+            let progress = if marked_complete { 100 } else { c.percentage(policy.formula) };
+            // Games without achievements can only be perfected by marking them as complete
+            let perfect = c.perfect || (marked_complete && (policy.targets_perfect || !c.has_achievements));
+            (c.app_id, GameProgress {
+                progress,
+                complete: progress >= policy.complete_threshold,
+                perfect,
+                completion: c,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,116 @@
+use api::achievement_fetch;
+use db::{achievement_schema_store, daily_challenge_store, daily_challenge_store::DailyChallenge, excluded_achievement_store, game_completion_cache};
+use chrono::{Days, Local, NaiveDate};
+use rand::prelude::*;
+
+use std::collections::HashSet;
+
+use crate::{exclusion_rules, roll, roll::Roller, settings};
+
+/// The seed for a day's roll, the configured seed mixed with the number of days since the epoch
+pub fn seed_for(day: NaiveDate) -> u64 {
+    let days = day.signed_duration_since(NaiveDate::default()).num_days() as u64;
+    settings::get_daily_challenge_seed() ^ days.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// Today's challenge, picked the first time it is asked for and kept for the rest of the day.
+/// Also checks whether today's and yesterday's challenges have been completed.
+/// None when no game with some progress has an achievement left to pick
+pub async fn get_daily_challenge(key : &str, steam_id : &str) -> Result<Option<DailyChallenge>, String> {
+    let today = Local::now().date_naive();
+    // A challenge finished late in the day may not be seen until the next day
+    let yesterday = today.checked_sub_days(Days::new(1)).ok_or("Today has no yesterday")?;
+    if let Some(c) = daily_challenge_store::get_daily_challenge(&yesterday).map_err(|e| e.to_string())? {
+        if !c.completed && is_achieved(key, steam_id, &c).await? {
+            daily_challenge_store::complete_daily_challenge(&yesterday).map_err(|e| e.to_string())?;
+        }
+    }
+    match daily_challenge_store::get_daily_challenge(&today).map_err(|e| e.to_string())? {
+        Some(mut c) => {
+            if !c.completed && is_achieved(key, steam_id, &c).await? {
+                daily_challenge_store::complete_daily_challenge(&today).map_err(|e| e.to_string())?;
+                c.completed = true;
+            }
+            Ok(Some(c))
+        },
+        // A new challenge is always unachieved, there is nothing to check
+        None => roll_challenge(key, steam_id, today).await,
+    }
+}
+
+/// The number of days in a row the challenge was completed, today only breaks the streak once it is over
+pub fn streak(today: NaiveDate) -> i32 {
+    let completed: HashSet<NaiveDate> = daily_challenge_store::get_daily_challenges().expect("Failed to load daily challenges")
+        .into_iter()
+        .filter(|c| c.completed)
+        .map(|c| c.date)
+        .collect();
+    let mut day = if completed.contains(&today) { Some(today) } else { today.checked_sub_days(Days::new(1)) };
+    let mut streak = 0;
+    while let Some(d) = day.filter(|d| completed.contains(d)) {
+        streak += 1;
+        day = d.checked_sub_days(Days::new(1));
+    }
+    streak
+}
+
+/// Pick from the unachieved achievements not excluded or matched by an exclusion rule of games with some progress, seeded by the day
+async fn roll_challenge(key : &str, steam_id : &str, day: NaiveDate) -> Result<Option<DailyChallenge>, String> {
+    let mut app_ids: Vec<i32> = game_completion_cache::get_game_completion().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|c| c.has_achievements && c.achieved > 0 && c.achieved < c.total)
+        .map(|c| c.app_id)
+        .collect();
+    let mut roller = Roller::new(Some(seed_for(day)));
+    while let Some(app_id) = roll::take_random(&mut app_ids, roller.rng()) {
+        let Some(player_achievements) = achievement_fetch::try_get_player_achievements(key, steam_id, &app_id).await? else {
+            continue;
+        };
+        let mut excluded: Vec<String> = excluded_achievement_store::get_excluded_achievements_for_app(&app_id).map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|e| e.achievement_name)
+            .collect();
+        exclusion_rules::fetch_rule_data(key, &app_id).await?;
+        let names: Vec<String> = player_achievements.achievements.iter().map(|a| a.apiname.clone()).collect();
+        excluded.extend(exclusion_rules::excluded_by_rules(&app_id, &names)?);
+        let mut candidates: Vec<String> = player_achievements.achievements.into_iter()
+            .filter(|a| a.achieved == 0 && !excluded.contains(&a.apiname))
+            .map(|a| a.apiname)
+            .collect();
+        candidates.sort();
+        let Some(achievement_name) = candidates.choose(roller.rng()).cloned() else {
+            continue;
+        };
+        // Use the cached schema for the name if there is one
+        let mut schema: Vec<(String, String, Option<String>)> = achievement_schema_store::get_schema_for_app(&app_id).map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|a| (a.achievement_name, a.display_name, a.description))
+            .collect();
+        if !schema.iter().any(|a| a.0 == achievement_name) {
+            schema = achievement_fetch::try_get_game_achievements(key, &app_id).await?
+                .into_iter()
+                .map(|a| (a.name, a.display_name, a.description))
+                .collect();
+        }
+        let (display_name, description) = schema.into_iter()
+            .find(|a| a.0 == achievement_name)
+            .map(|a| (a.1, a.2))
+            .unwrap_or((achievement_name.clone(), None));
+        let challenge = DailyChallenge {
+            date: day,
+            app_id,
+            achievement_name,
+            display_name,
+            description,
+            completed: false,
+        };
+        daily_challenge_store::save_daily_challenge(&challenge).map_err(|e| e.to_string())?;
+        return Ok(Some(challenge));
+    }
+    Ok(None)
+}
+
+async fn is_achieved(key : &str, steam_id : &str, challenge: &DailyChallenge) -> Result<bool, String> {
+    Ok(achievement_fetch::try_get_player_achievements(key, steam_id, &challenge.app_id).await?
+        .is_some_and(|p| p.achievements.iter().any(|a| a.apiname == challenge.achievement_name && a.achieved == 1)))
+}
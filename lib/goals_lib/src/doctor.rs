@@ -0,0 +1,243 @@
+use api::game_fetch;
+use db::{achievement_rarity_store, achievement_schema_store, achievement_store, achievement_unlock_store, collection_store, daily_challenge_store, excluded_achievement_store, exclusion_rule_store, game_completion_cache, game_pick_store, game_target_store, goal_set_store, hours_to_beat_store, note_store, progress_snapshot_store, refresh_checkpoint_store, request_store, schema_change_store, settings_store, skipped_achievement_store, steam_id_store};
+use db_lib::db_manager;
+
+use std::{collections::{HashMap, HashSet}, fmt};
+
+// Tables from before a migration, the migration to run to clear them
+const LEGACY_TABLES: [(&str, &str); 4] = [
+    ("steam_achievements", "add_display_name_and_description_and_last_played_to_achievement_store"),
+    ("steam_key", "drop_key_store"),
+    ("steam_game_completion", "drop_game_completion_v_1"),
+    ("steam_request_count", "drop_request_count_v_1"),
+];
+
+// Columns added by a migration, the migration to run to add them
+const MIGRATED_COLUMNS: [(&str, &str, &str); 5] = [
+    ("excluded_steam_achievements", "reason", "add_reason_to_excluded_achievement_store"),
+    ("steam_achievements_v_2", "priority", "add_priority_and_due_date_to_achievement_store"),
+    ("steam_achievements_v_2", "due_date", "add_priority_and_due_date_to_achievement_store"),
+    ("steam_achievements_v_2", "seed", "add_seed_to_achievement_store"),
+    ("achievement_schemas", "hidden", "add_hidden_to_achievement_schemas"),
+];
+
+// Unique indexes added by a migration, the table they are on and the migration to run to add them
+const MIGRATED_INDEXES: [(&str, &str, &str); 2] = [
+    ("steam_achievements_v_2_app_achievement", "steam_achievements_v_2", "add_unique_goal_and_exclusion_constraints"),
+    ("excluded_steam_achievements_app_achievement", "excluded_steam_achievements", "add_unique_goal_and_exclusion_constraints"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    MissingTable(String),
+    LegacyTable { table: String, migration: String },
+    MissingColumn { table: String, column: String, migration: String },
+    MissingIndex { index: String, migration: String },
+    OrphanedGoal { id: i32, app_id: i32, achievement_name: String },
+    DuplicateGoal { id: i32, app_id: i32, achievement_name: String },
+    ExcludedGoal { id: i32, app_id: i32, achievement_name: String },
+    OrphanedExclusion { id: i32, app_id: i32, achievement_name: String },
+    DuplicateExclusion { id: i32, app_id: i32, achievement_name: String },
+    UnindexedGoal { id: i32, app_id: i32, achievement_name: String },
+    OrphanedTarget { app_id: i32 },
+    StaleCache { app_id: i32 },
+}
+
+impl Problem {
+    /// Problems that need the migrations binary can't be fixed from here
+    pub fn can_fix(&self) -> bool {
+        !matches!(self, Problem::LegacyTable { .. } | Problem::MissingColumn { .. } | Problem::MissingIndex { .. })
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MissingTable(table) => write!(f, "Table {table} is missing"),
+            Problem::LegacyTable { table, migration } => write!(f, "Table {table} is from an old version, run the migration {migration}"),
+            Problem::MissingColumn { table, column, migration } => write!(f, "Table {table} is missing the column {column}, run the migration {migration}"),
+            Problem::MissingIndex { index, migration } => write!(f, "Index {index} is missing, run the migration {migration}"),
+            Problem::OrphanedGoal { id, app_id, achievement_name } => write!(f, "Goal {achievement_name} [{id}] is for the game {app_id} which is no longer owned"),
+            Problem::DuplicateGoal { id, app_id, achievement_name } => write!(f, "Goal {achievement_name} [{id}] for the game {app_id} is a duplicate"),
+            Problem::ExcludedGoal { id, app_id, achievement_name } => write!(f, "Goal {achievement_name} [{id}] for the game {app_id} is also excluded"),
+            Problem::OrphanedExclusion { id, app_id, achievement_name } => write!(f, "Exclusion {achievement_name} [{id}] is for the game {app_id} which is no longer owned"),
+            Problem::DuplicateExclusion { id, app_id, achievement_name } => write!(f, "Exclusion {achievement_name} [{id}] for the game {app_id} is a duplicate"),
+            Problem::UnindexedGoal { id, app_id, achievement_name } => write!(f, "Goal {achievement_name} [{id}] for the game {app_id} is missing from the search index"),
+            Problem::OrphanedTarget { app_id } => write!(f, "Target for the game {app_id} which is no longer owned"),
+            Problem::StaleCache { app_id } => write!(f, "Cached completion for the game {app_id} is out of date"),
+        }
+    }
+}
+
+/// Scan every store for data that will cause errors or wrong results
+pub async fn diagnose(key : &str, steam_id : &str) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = Vec::new();
+
+    // Check the schema first, the other checks can't run on an unmigrated database
+    let tables = [
+        "steam_id_store",
+        "steam_achievements_v_2",
+        "excluded_steam_achievements",
+        "steam_request_log",
+        "steam_game_completion_v_2",
+        "game_targets",
+        "collections",
+        "collection_games",
+        "notes",
+        "settings",
+        "skipped_achievements",
+        "progress_snapshots",
+        "achievement_schemas",
+        "achievement_schema_apps",
+        "achievement_search",
+        "refresh_checkpoint",
+        "schema_changes",
+        "schema_checks",
+        "daily_challenges",
+        "achievement_rarity",
+        "achievement_rarity_apps",
+        "goal_sets",
+        "goal_set_items",
+        "game_picks",
+        "exclusion_rules",
+        "achievement_unlocks",
+        "achievement_unlock_apps",
+        "hours_to_beat",
+    ];
+    for table in tables {
+        if !db_manager::table_exists(table).expect("Failed to check table") {
+            problems.push(Problem::MissingTable(table.to_string()));
+        }
+    }
+    for (table, migration) in LEGACY_TABLES {
+        if db_manager::table_exists(table).expect("Failed to check table") {
+            problems.push(Problem::LegacyTable { table: table.to_string(), migration: migration.to_string() });
+        }
+    }
+    for (table, column, migration) in MIGRATED_COLUMNS {
+        if db_manager::table_exists(table).expect("Failed to check table") && !db_manager::column_exists(table, column).expect("Failed to check column") {
+            problems.push(Problem::MissingColumn { table: table.to_string(), column: column.to_string(), migration: migration.to_string() });
+        }
+    }
+    for (index, table, migration) in MIGRATED_INDEXES {
+        if db_manager::table_exists(table).expect("Failed to check table") && !db_manager::index_exists(index).expect("Failed to check index") {
+            problems.push(Problem::MissingIndex { index: index.to_string(), migration: migration.to_string() });
+        }
+    }
+    // A missing index doesn't stop the other checks, the duplicates it would prevent are reported below
+    if problems.iter().any(|p| matches!(p, Problem::LegacyTable { .. } | Problem::MissingColumn { .. })) {
+        return problems;
+    }
+
+    let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(key, steam_id).await
+        .into_iter()
+        .map(|g| (g.appid, g))
+        .collect();
+    // A private profile returns no games, everything would look orphaned
+    let check_owned = !owned_games.is_empty();
+
+    let exclusions = excluded_achievement_store::get_excluded_achievements().expect("Failed to load exclusions");
+    let excluded: HashSet<(i32, String)> = exclusions.iter()
+        .map(|e| (e.app_id, e.achievement_name.clone()))
+        .collect();
+    let mut seen_exclusions: HashSet<(i32, String)> = HashSet::new();
+    let mut sorted_exclusions = exclusions;
+    sorted_exclusions.sort_by_key(|e| e.id);
+    for e in sorted_exclusions {
+        if !seen_exclusions.insert((e.app_id, e.achievement_name.clone())) {
+            problems.push(Problem::DuplicateExclusion { id: e.id, app_id: e.app_id, achievement_name: e.achievement_name });
+        }
+        else if check_owned && !owned_games.contains_key(&e.app_id) {
+            problems.push(Problem::OrphanedExclusion { id: e.id, app_id: e.app_id, achievement_name: e.achievement_name });
+        }
+    }
+
+    // Goals saved before they were indexed on save can't be searched
+    let indexed: HashSet<(i32, String)> = achievement_schema_store::get_indexed_achievements().expect("Failed to load the search index")
+        .into_iter()
+        .collect();
+    let mut goals = achievement_store::get_achievements().expect("Failed to load achievements");
+    goals.sort_by_key(|a| a.id);
+    let mut seen_goals: HashSet<(i32, String)> = HashSet::new();
+    for g in goals {
+        let key = (g.app_id, g.achievement_name.clone());
+        if !seen_goals.insert(key.clone()) {
+            problems.push(Problem::DuplicateGoal { id: g.id, app_id: g.app_id, achievement_name: g.achievement_name });
+        }
+        else if check_owned && !owned_games.contains_key(&g.app_id) {
+            problems.push(Problem::OrphanedGoal { id: g.id, app_id: g.app_id, achievement_name: g.achievement_name });
+        }
+        else if excluded.contains(&key) {
+            problems.push(Problem::ExcludedGoal { id: g.id, app_id: g.app_id, achievement_name: g.achievement_name });
+        }
+        else if !indexed.contains(&key) {
+            problems.push(Problem::UnindexedGoal { id: g.id, app_id: g.app_id, achievement_name: g.achievement_name });
+        }
+    }
+
+    if check_owned {
+        for t in game_target_store::get_game_targets().expect("Failed to load targets") {
+            if !owned_games.contains_key(&t.app_id) {
+                problems.push(Problem::OrphanedTarget { app_id: t.app_id });
+            }
+        }
+        for c in game_completion_cache::get_game_completion().expect("Failed to load completed games") {
+            if owned_games.get(&c.app_id).is_none_or(|g| g.last_played != c.last_played) {
+                problems.push(Problem::StaleCache { app_id: c.app_id });
+            }
+        }
+    }
+
+    problems
+}
+
+pub fn fix(problem: &Problem) -> Result<(), String> {
+    match problem {
+        Problem::MissingTable(table) => {
+            match table.as_str() {
+                "steam_id_store" => steam_id_store::ensure_table(),
+                "steam_achievements_v_2" => achievement_store::ensure_table(),
+                "excluded_steam_achievements" => excluded_achievement_store::ensure_table(),
+                "steam_request_log" => request_store::ensure_table(),
+                "steam_game_completion_v_2" => game_completion_cache::ensure_table(),
+                "game_targets" => game_target_store::ensure_table(),
+                "collections" | "collection_games" => collection_store::ensure_table(),
+                "notes" => note_store::ensure_table(),
+                "settings" => settings_store::ensure_table(),
+                "skipped_achievements" => skipped_achievement_store::ensure_table(),
+                "progress_snapshots" => progress_snapshot_store::ensure_table(),
+                "achievement_schemas" | "achievement_schema_apps" | "achievement_search" => achievement_schema_store::ensure_table(),
+                "refresh_checkpoint" => refresh_checkpoint_store::ensure_table(),
+                "schema_changes" | "schema_checks" => schema_change_store::ensure_table(),
+                "daily_challenges" => daily_challenge_store::ensure_table(),
+                "achievement_rarity" | "achievement_rarity_apps" => achievement_rarity_store::ensure_table(),
+                "goal_sets" | "goal_set_items" => goal_set_store::ensure_table(),
+                "game_picks" => game_pick_store::ensure_table(),
+                "exclusion_rules" => exclusion_rule_store::ensure_table(),
+                "achievement_unlocks" | "achievement_unlock_apps" => achievement_unlock_store::ensure_table(),
+                "hours_to_beat" => hours_to_beat_store::ensure_table(),
+                _ => return Err(format!("Unknown table {table}")),
+            }.map_err(|e| e.to_string())
+        },
+        Problem::LegacyTable { migration, .. } | Problem::MissingColumn { migration, .. } | Problem::MissingIndex { migration, .. } => {
+            Err(format!("Run the migration {migration} with steam-rand-migrations"))
+        },
+        Problem::OrphanedGoal { id, .. } | Problem::DuplicateGoal { id, .. } | Problem::ExcludedGoal { id, .. } => {
+            achievement_store::delete_achievement(id).map_err(|e| e.to_string())
+        },
+        Problem::OrphanedExclusion { id, .. } | Problem::DuplicateExclusion { id, .. } => {
+            excluded_achievement_store::delete_excluded_achievement(id).map_err(|e| e.to_string())
+        },
+        Problem::UnindexedGoal { id, .. } => {
+            let goal = achievement_store::get_achievement(id).map_err(|e| e.to_string())?
+                .ok_or(format!("No goal with the id {id}"))?;
+            achievement_schema_store::index_achievement(&goal.app_id, &goal.achievement_name, &goal.display_name, &goal.description).map_err(|e| e.to_string())
+        },
+        Problem::OrphanedTarget { app_id } => {
+            game_target_store::delete_game_target(app_id).map_err(|e| e.to_string())
+        },
+        Problem::StaleCache { app_id } => {
+            game_completion_cache::delete_game_completion(app_id).map_err(|e| e.to_string())
+        },
+    }
+}
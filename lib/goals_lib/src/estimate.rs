@@ -0,0 +1,133 @@
+use api::game_fetch::Game;
+use db::{achievement_schema_store, achievement_store, achievement_unlock_store, daily_challenge_store, game_completion_cache, request_store};
+use chrono::{Days, Local, NaiveDate};
+
+use std::{collections::{HashMap, HashSet}, fmt};
+
+use crate::{exclusion_rules, schema_change, score};
+
+/// The number of Steam API requests an operation will make, worked out from the local caches
+#[derive(Debug, Clone, Copy)]
+pub struct RequestEstimate {
+    pub requests: i32,
+    pub remaining: i32, // Left in today's quota
+}
+
+impl RequestEstimate {
+    pub fn new(requests: i32) -> Self {
+        RequestEstimate {
+            requests,
+            remaining: request_store::get_remaining().expect("Failed to load the request count"),
+        }
+    }
+
+    pub fn fits(&self) -> bool {
+        self.requests <= self.remaining
+    }
+
+    /// Refuse to start when the operation would run out of requests part way through
+    pub fn check(&self) -> Result<(), String> {
+        if self.fits() {
+            Ok(())
+        }
+        else {
+            Err(format!("This needs about {} requests but only {} are left today, try again after the quota resets", self.requests, self.remaining))
+        }
+    }
+}
+
+impl fmt::Display for RequestEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "This needs about {} requests, {} are left today", self.requests, self.remaining)
+    }
+}
+
+/// Requests for goals::refresh_game_completion_cache, one per played game not cached since it was last played
+/// or with unlocks not cached, plus the schema and rarity the exclusion rules need for it.
+/// One more per complete game whose schema is checked for new achievements
+pub fn completion_refresh(games: &[Game]) -> i32 {
+    let cache: HashMap<i32, (i64, i32)> = game_completion_cache::get_game_completion().expect("Failed to load completed games")
+        .into_iter()
+        .map(|c| (c.app_id, (c.last_played, c.achieved)))
+        .collect();
+    let unlocks_cached: HashSet<i32> = achievement_unlock_store::get_cached_app_ids().expect("Failed to load cached unlocks")
+        .into_iter()
+        .collect();
+    let to_refresh: Vec<i32> = games.iter()
+        .filter(|g| g.playtime_forever > 0)
+        .filter(|g| cache.get(&g.appid).is_none_or(|(last_played, achieved)| *last_played != g.last_played || (*achieved > 0 && !unlocks_cached.contains(&g.appid))))
+        .map(|g| g.appid)
+        .collect();
+    to_refresh.len() as i32
+        + exclusion_rules::rule_data_requests(&to_refresh).expect("Failed to load the exclusion rules")
+        + schema_change::apps_to_check(games).len() as i32
+}
+
+/// Requests for goals::get_and_sync_completed_achievements, the owned games and one per game played since its goals were checked.
+/// The autopilot uses more for each completed goal it replaces
+pub fn goal_sync(games: &[Game]) -> i32 {
+    let last_played: HashMap<i32, i64> = games.iter().map(|g| (g.appid, g.last_played)).collect();
+    let apps: HashSet<i32> = achievement_store::get_achievements().expect("Failed to load achievements")
+        .into_iter()
+        .filter(|a| last_played.get(&a.app_id).is_some_and(|l| *l != a.last_played))
+        .map(|a| a.app_id)
+        .collect();
+    1 + apps.len() as i32
+}
+
+/// Requests for search::index_schemas, one per played game without a cached schema
+pub fn schema_index(games: &[Game]) -> i32 {
+    let cached: HashSet<i32> = achievement_schema_store::get_cached_app_ids().expect("Failed to load cached schemas")
+        .into_iter()
+        .collect();
+    games.iter()
+        .filter(|g| g.playtime_forever > 0 && !cached.contains(&g.appid))
+        .count() as i32
+}
+
+/// Requests for search::search, only an unachieved search makes requests, one per matching game
+pub fn search(query: &str, unachieved_only: bool) -> i32 {
+    if !unachieved_only {
+        return 0;
+    }
+    achievement_schema_store::search(query).expect("Failed to search achievements")
+        .into_iter()
+        .map(|m| m.app_id)
+        .collect::<HashSet<i32>>()
+        .len() as i32
+}
+
+/// Requests for goals::get_random_achievement_for_game, the player achievements and the schema.
+/// A rarity exclusion rule adds one the first time a game is rolled
+pub fn random_achievement() -> i32 {
+    2
+}
+
+/// Requests for daily::get_daily_challenge, one for each open challenge from today and yesterday,
+/// or the player achievements and the schema when today's has not been picked yet
+pub fn daily_challenge() -> i32 {
+    let today = Local::now().date_naive();
+    let open = |day: Option<NaiveDate>| day
+        .and_then(|d| daily_challenge_store::get_daily_challenge(&d).expect("Failed to load the daily challenge"))
+        .map(|c| if c.completed { 0 } else { 1 });
+    open(today.checked_sub_days(Days::new(1))).unwrap_or(0) + open(Some(today)).unwrap_or(2)
+}
+
+/// Requests for bingo::generate_card, the player achievements and the schema for each square
+/// and the rarity as well when balancing, more when games have nothing left to pick
+pub fn bingo(size: usize, balance_rarity: bool) -> i32 {
+    let per_square = if balance_rarity { 3 } else { 2 };
+    (size * size) as i32 * per_square
+}
+
+/// Requests for multi_roll::roll_goals, the player achievements and the schema for each goal
+/// and the rarity as well when limited by it, more when games have nothing left to pick
+pub fn multi_roll(count: usize, limits_rarity: bool) -> i32 {
+    let per_goal = if limits_rarity { 3 } else { 2 };
+    count as i32 * per_goal
+}
+
+/// Requests for score::fetch_missing_rarity, one per game with unlocks and no rarity cached
+pub fn scores() -> i32 {
+    score::apps_missing_rarity().expect("Failed to load cached unlocks").len() as i32
+}
@@ -0,0 +1,178 @@
+use api::{achievement_fetch, global_achievement_fetch};
+use db::{achievement_rarity_store, achievement_schema_store, achievement_schema_store::SchemaAchievement, excluded_achievement_store::ExclusionReason, exclusion_rule_store, exclusion_rule_store::{ExclusionRule, RuleKind}, game_completion_cache};
+
+use regex::{Regex, RegexBuilder};
+
+use std::collections::{HashMap, HashSet};
+
+/// Check the value suits the kind of rule, returns the value to save
+pub fn validate(kind: &RuleKind, value: &Option<String>) -> Result<String, String> {
+    let value = value.clone().unwrap_or_default().trim().to_string();
+    match kind {
+        RuleKind::Pattern => {
+            if value.is_empty() {
+                return Err("A pattern rule needs a regex to match".to_string());
+            }
+            compile_pattern(&value)?;
+        },
+        RuleKind::Rarity => {
+            let percent: f64 = value.parse().map_err(|_| "A rarity rule needs a percent, such as 1.5".to_string())?;
+            if !(0.0..=100.0).contains(&percent) {
+                return Err("The percent must be between 0 and 100".to_string());
+            }
+        },
+        RuleKind::Hidden => return Ok(String::new()),
+        RuleKind::App => {
+            value.parse::<i32>().map_err(|_| "An app rule needs an app id".to_string())?;
+        },
+    }
+    Ok(value)
+}
+
+/// Case insensitive, the pattern can match anywhere in the text
+fn compile_pattern(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Not a valid pattern: {e}"))
+}
+
+/// A rule ready to match, the regex of a pattern rule is compiled once rather than for every achievement
+struct CompiledRule<'a> {
+    rule: &'a ExclusionRule,
+    pattern: Option<Regex>,
+}
+
+impl<'a> CompiledRule<'a> {
+    fn new(rule: &'a ExclusionRule) -> Result<Self, String> {
+        let pattern = if rule.kind == RuleKind::Pattern { Some(compile_pattern(&rule.value)?) } else { None };
+        Ok(CompiledRule { rule, pattern })
+    }
+
+    /// Whether the rule excludes the achievement, schema and rarity are None when they are not cached
+    fn matches(&self, app_id: &i32, schema: Option<&SchemaAchievement>, rarity: Option<f64>) -> bool {
+        match self.rule.kind {
+            RuleKind::Pattern => schema.is_some_and(|a| self.pattern.as_ref().is_some_and(|p| {
+                p.is_match(&a.achievement_name)
+                    || p.is_match(&a.display_name)
+                    || a.description.as_ref().is_some_and(|d| p.is_match(d))
+            })),
+            RuleKind::Rarity => self.rule.value.parse::<f64>().is_ok_and(|threshold| rarity.is_some_and(|r| r < threshold)),
+            RuleKind::Hidden => schema.is_some_and(|a| a.hidden),
+            RuleKind::App => self.rule.value.parse::<i32>().is_ok_and(|a| a == *app_id),
+        }
+    }
+}
+
+/// The achievements of the game given that the rules exclude.
+/// Only the cached schema and rarity are used, rules needing them don't match games without them cached
+pub fn excluded_by_rules(app_id: &i32, achievement_names: &[String]) -> Result<HashSet<String>, String> {
+    let rules = exclusion_rule_store::get_exclusion_rules().map_err(|e| e.to_string())?;
+    if rules.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let rules: Vec<CompiledRule> = rules.iter().map(CompiledRule::new).collect::<Result<_, _>>()?;
+    let schema: HashMap<String, SchemaAchievement> = achievement_schema_store::get_schema_for_app(app_id).map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|a| (a.achievement_name.clone(), a))
+        .collect();
+    let rarity: HashMap<String, f64> = achievement_rarity_store::get_rarity_for_app(app_id).map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| (r.achievement_name, r.percent))
+        .collect();
+    Ok(achievement_names.iter()
+        .filter(|n| rules.iter().any(|r| r.matches(app_id, schema.get(*n), rarity.get(*n).copied())))
+        .cloned()
+        .collect())
+}
+
+/// Fetch the schema and rarity of the game when a rule needs them and they are not cached, used before a roll or a completion refresh
+pub async fn fetch_rule_data(key : &str, app_id: &i32) -> Result<(), String> {
+    let (needs_schema, needs_rarity) = rule_data_needed(std::slice::from_ref(app_id))?;
+    if !needs_schema.is_empty() {
+        achievement_fetch::try_get_game_achievements(key, app_id).await?;
+    }
+    if !needs_rarity.is_empty() {
+        global_achievement_fetch::try_get_global_achievement_percentages(app_id).await?;
+    }
+    Ok(())
+}
+
+/// The requests fetch_rule_data makes for the games given
+pub fn rule_data_requests(app_ids: &[i32]) -> Result<i32, String> {
+    let (needs_schema, needs_rarity) = rule_data_needed(app_ids)?;
+    Ok((needs_schema.len() + needs_rarity.len()) as i32)
+}
+
+/// The games whose schema and whose rarity the rules need and are not cached
+fn rule_data_needed(app_ids: &[i32]) -> Result<(Vec<i32>, Vec<i32>), String> {
+    let rules = exclusion_rule_store::get_exclusion_rules().map_err(|e| e.to_string())?;
+    let mut needs_schema: Vec<i32> = Vec::new();
+    if rules.iter().any(|r| r.kind == RuleKind::Pattern || r.kind == RuleKind::Hidden) {
+        let cached: HashSet<i32> = achievement_schema_store::get_cached_app_ids().map_err(|e| e.to_string())?.into_iter().collect();
+        needs_schema.extend(app_ids.iter().filter(|a| !cached.contains(a)));
+    }
+    let mut needs_rarity: Vec<i32> = Vec::new();
+    if rules.iter().any(|r| r.kind == RuleKind::Rarity) {
+        let cached: HashSet<i32> = achievement_rarity_store::get_cached_app_ids().map_err(|e| e.to_string())?.into_iter().collect();
+        needs_rarity.extend(app_ids.iter().filter(|a| !cached.contains(a)));
+    }
+    Ok((needs_schema, needs_rarity))
+}
+
+/// The cached achievements a rule would exclude, without saving it
+pub fn preview(kind: &RuleKind, value: &Option<String>) -> Result<Vec<SchemaAchievement>, String> {
+    let rule = ExclusionRule {
+        id: 0,
+        kind: *kind,
+        value: validate(kind, value)?,
+        reason: None,
+    };
+    matching(&rule)
+}
+
+fn matching(rule: &ExclusionRule) -> Result<Vec<SchemaAchievement>, String> {
+    let rule = CompiledRule::new(rule)?;
+    let rarity: HashMap<(i32, String), f64> = achievement_rarity_store::get_all_rarity().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| ((r.app_id, r.achievement_name), r.percent))
+        .collect();
+    Ok(achievement_schema_store::get_schemas().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|a| rule.matches(&a.app_id, Some(a), rarity.get(&(a.app_id, a.achievement_name.clone())).copied()))
+        .collect())
+}
+
+/// Save the rule, the cached completion of every game it matches is dropped so the next refresh counts it.
+/// Returns the id of the rule
+pub fn save_rule(kind: &RuleKind, value: &Option<String>, reason: &Option<ExclusionReason>) -> Result<i32, String> {
+    let value = validate(kind, value)?;
+    let id = exclusion_rule_store::save_exclusion_rule(kind, &value, reason).map_err(|e| e.to_string())?;
+    let rule = exclusion_rule_store::get_exclusion_rules().map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|r| r.id == id)
+        .ok_or("Failed to load the new rule".to_string())?;
+    clear_completion(&rule)?;
+    Ok(id)
+}
+
+pub fn delete_rule(id: &i32) -> Result<ExclusionRule, String> {
+    let rule = exclusion_rule_store::get_exclusion_rules().map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|r| r.id == *id)
+        .ok_or(format!("No exclusion rule with the id {id}"))?;
+    exclusion_rule_store::delete_exclusion_rule(id).map_err(|e| e.to_string())?;
+    clear_completion(&rule)?;
+    Ok(rule)
+}
+
+fn clear_completion(rule: &ExclusionRule) -> Result<(), String> {
+    let mut app_ids: HashSet<i32> = matching(rule)?.into_iter().map(|a| a.app_id).collect();
+    if rule.kind == RuleKind::App {
+        app_ids.extend(rule.value.parse::<i32>().ok());
+    }
+    for app_id in app_ids {
+        game_completion_cache::delete_game_completion(&app_id).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use api::{achievement_fetch, game_fetch::Game, global_achievement_fetch};
+use db::{achievement_rarity_store, achievement_schema_store, achievement_store, excluded_achievement_store, game_pick_store, goal_set_store, goal_set_store::{GoalSetItem, GoalSetKind}, skipped_achievement_store};
+use chrono::Local;
+use rand::prelude::*;
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{exclusion_rules, roll::Roller};
+
+/// How the percent of players with an achievement limits what can be picked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RarityRule {
+    Any,
+    /// Pick from the range when the game has something in it, otherwise from anything
+    Prefer((f64, f64)),
+    /// Only pick from the range
+    Require((f64, f64)),
+}
+
+/// Save the goal set and add each of its items to the goals, each game counts as picked for the cooldown. Returns the id of the set
+pub fn save_with_goals(name: &str, kind: &GoalSetKind, size: i32, items: &[GoalSetItem], games: &[Game], roller: &Roller) -> Result<i32, String> {
+    let today = Local::now().date_naive();
+    let set_id = goal_set_store::save_goal_set(name, kind, &size, &Some(roller.stored_seed()), &today, items)
+        .map_err(|e| e.to_string())?;
+    for (item, game) in items.iter().zip(games.iter()) {
+        achievement_store::save_achievement(&item.achievement_name, &item.display_name, &item.description, &item.app_id, &game.last_played, &Some(roller.stored_seed()))
+            .map_err(|e| e.to_string())?;
+        game_pick_store::save_game_pick(&item.app_id, &today).map_err(|e| e.to_string())?;
+    }
+    Ok(set_id)
+}
+
+/// Pick an unachieved achievement from the game, None when the game has nothing left to pick
+pub async fn roll_item(key : &str, steam_id : &str, game: &Game, position: i32, rarity_rule: RarityRule, roller: &mut Roller) -> Result<Option<GoalSetItem>, String> {
+    let Some(player_achievements) = achievement_fetch::try_get_player_achievements(key, steam_id, &game.appid).await? else {
+        return Ok(None);
+    };
+    let goals: HashSet<String> = achievement_store::get_achievements_for_app(&game.appid).map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|a| a.achievement_name)
+        .collect();
+    let mut excluded: HashSet<String> = excluded_achievement_store::get_excluded_achievements_for_app(&game.appid).map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|e| e.achievement_name)
+        .collect();
+    exclusion_rules::fetch_rule_data(key, &game.appid).await?;
+    let names: Vec<String> = player_achievements.achievements.iter().map(|a| a.apiname.clone()).collect();
+    excluded.extend(exclusion_rules::excluded_by_rules(&game.appid, &names)?);
+    let today = Local::now().date_naive();
+    let skipped: HashSet<String> = skipped_achievement_store::get_skipped_achievements_for_app(&game.appid).map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|s| s.on_cooldown(today))
+        .map(|s| s.achievement_name)
+        .collect();
+    let mut candidates: Vec<String> = player_achievements.achievements.into_iter()
+        .filter(|a| a.achieved == 0)
+        .map(|a| a.apiname)
+        .filter(|a| !goals.contains(a) && !excluded.contains(a) && !skipped.contains(a))
+        .collect();
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+    candidates.sort();
+
+    let rarity = get_rarity(&game.appid, rarity_rule != RarityRule::Any).await?;
+    let in_range = |(min, max): (f64, f64)| -> Vec<String> {
+        candidates.iter()
+            .filter(|c| rarity.get(*c).is_some_and(|p| *p >= min && *p <= max))
+            .cloned()
+            .collect()
+    };
+    let pool = match rarity_rule {
+        RarityRule::Any => candidates.clone(),
+        RarityRule::Prefer(range) => Some(in_range(range)).filter(|p| !p.is_empty()).unwrap_or(candidates.clone()),
+        RarityRule::Require(range) => in_range(range),
+    };
+    let Some(achievement_name) = pool.choose(roller.rng()).cloned() else {
+        return Ok(None);
+    };
+
+    // Use the cached schema for the name and icons if there is one
+    let mut schema = achievement_schema_store::get_schema_for_app(&game.appid).map_err(|e| e.to_string())?;
+    if !schema.iter().any(|a| a.achievement_name == achievement_name) {
+        achievement_fetch::try_get_game_achievements(key, &game.appid).await?;
+        schema = achievement_schema_store::get_schema_for_app(&game.appid).map_err(|e| e.to_string())?;
+    }
+    let Some(details) = schema.into_iter().find(|a| a.achievement_name == achievement_name) else {
+        return Ok(None);
+    };
+    Ok(Some(GoalSetItem {
+        position,
+        app_id: game.appid,
+        rarity: rarity.get(&achievement_name).copied(),
+        achievement_name,
+        display_name: details.display_name,
+        description: details.description,
+        icon: details.icon,
+        icon_gray: details.icon_gray,
+        completed: false,
+    }))
+}
+
+/// The cached rarity of each achievement in the game, fetched when missing and needed
+async fn get_rarity(app_id: &i32, fetch: bool) -> Result<HashMap<String, f64>, String> {
+    let cached = achievement_rarity_store::get_cached_app_ids().map_err(|e| e.to_string())?.contains(app_id);
+    if fetch && !cached {
+        global_achievement_fetch::try_get_global_achievement_percentages(app_id).await?;
+    }
+    Ok(achievement_rarity_store::get_rarity_for_app(app_id).map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| (r.achievement_name, r.percent))
+        .collect())
+}
@@ -1,7 +1,10 @@
 use api::{achievement_fetch::{self, GameAchievement}, game_fetch, game_fetch::Game};
-use db::{achievement_store, excluded_achievement_store, game_completion_cache, game_target_store};
+use db::{achievement_store, achievement_unlock_store, collection_store, excluded_achievement_store, game_completion_cache, goal_set_store, refresh_checkpoint_store, skipped_achievement_store};
+use chrono::{Days, Local, NaiveDate};
 
-use std::{collections::HashMap};
+use crate::{autopilot, exclusion_rules, schema_change, settings, snapshot};
+
+use std::{collections::{HashMap, HashSet}, sync::atomic::{AtomicBool, Ordering}};
 use rand::prelude::*;
 
 pub async fn get_and_sync_completed_achievements(key : &str, steam_id : &str) -> Vec<achievement_store::Achievement> {
@@ -11,8 +14,10 @@ pub async fn get_and_sync_completed_achievements(key : &str, steam_id : &str) ->
     let owned_games: HashMap<i32, game_fetch::Game> = game_fetch::get_owned_games(key, steam_id).await.iter().map(|n| (n.appid, n.clone())).collect();
     let mut achievement_completed: Vec<achievement_store::Achievement> = Vec::new();
     for a in achievements {
-        // Get the game out of the map
-        let game = owned_games.get(&a.app_id).unwrap();
+        // Get the game out of the map, goals for games no longer owned are left for the doctor to report
+        let Some(game) = owned_games.get(&a.app_id) else {
+            continue;
+        };
         // Check if the last_played has changed
         if game.last_played != a.last_played {
             // Check if the app is already loaded (PlayerAchievements)
@@ -20,14 +25,19 @@ pub async fn get_and_sync_completed_achievements(key : &str, steam_id : &str) ->
             let loaded_player: &achievement_fetch::PlayerAchievements = if let Some(a) = player_achievements {
                 a
             }
-            else {
-                let player = achievement_fetch::get_player_achievements(key, steam_id, &a.app_id).await.expect("Somehow a game with no achievements has ended up with one?!?");
+            else if let Some(player) = achievement_fetch::get_player_achievements(key, steam_id, &a.app_id).await {
                 app_player_achievement_map.insert(a.app_id, player);
                 app_player_achievement_map.get(&a.app_id).unwrap()
+            }
+            else {
+                // The game no longer reports achievements
+                continue;
             };
             // Remove any that are already completed
-            if loaded_player.achievements.iter().find(|x| x.apiname==a.achievement_name).unwrap().achieved == 1 {
+            if loaded_player.achievements.iter().find(|x| x.apiname==a.achievement_name).is_some_and(|x| x.achieved == 1) {
                 achievement_store::delete_achievement(&a.id).expect("Failed to delete achievement");
+                // Mark the square on any bingo card it is part of
+                goal_set_store::complete_item(&a.app_id, &a.achievement_name).expect("Failed to mark the goal set");
                 achievement_completed.push(a);
             }
             // Update last_played to avoid checking again
@@ -36,116 +46,259 @@ pub async fn get_and_sync_completed_achievements(key : &str, steam_id : &str) ->
             }
         }
     }
+    autopilot::refill(key, steam_id, &achievement_completed, &owned_games).await;
     achievement_completed
 }
 
-pub async fn get_random_achievement_for_game(key : &str, steam_id : &str, game: &Game) -> Option<GameAchievement> {
+/// Err when adding this many goals would go over the goal limit
+pub fn check_goal_limit(adding: usize) -> Result<(), String> {
+    if let Some(limit) = settings::get_goal_limit() {
+        let active = achievement_store::count_achievements(None).map_err(|e| e.to_string())?;
+        if active + adding as i32 > limit {
+            return Err(format!("You have {active} goals, adding {adding} would go over the limit of {limit}"));
+        }
+    }
+    Ok(())
+}
+
+/// Err when adding a goal for the game would go over the limit per game
+pub fn check_game_goal_limit(app_id: &i32) -> Result<(), String> {
+    if let Some(limit) = settings::get_game_goal_limit() {
+        let active = achievement_store::count_achievements(Some(app_id)).map_err(|e| e.to_string())?;
+        if active >= limit {
+            return Err(format!("The game already has {active} goals, the limit per game is {limit}"));
+        }
+    }
+    Ok(())
+}
+
+/// Keep only the games in the named collection, all games are kept when no collection is given
+pub fn filter_to_collection(games: Vec<Game>, collection: &Option<String>) -> Vec<Game> {
+    if let Some(name) = collection {
+        let app_ids: Vec<i32> = collection_store::get_collection(name)
+            .expect("Failed to load collection")
+            .map(|c| collection_store::get_app_ids_for_collection(&c.id).expect("Failed to load collection games"))
+            .unwrap_or_default();
+        games.into_iter()
+            .filter(|g| app_ids.contains(&g.appid))
+            .collect()
+    }
+    else {
+        games
+    }
+}
+
+/// Pick a random achievement the player has not got yet, the pick only depends on the rng and the achievements left
+pub async fn get_random_achievement_for_game<R: Rng + ?Sized>(key : &str, steam_id : &str, game: &Game, rng: &mut R) -> Option<GameAchievement> {
+    try_get_random_achievement_for_game(key, steam_id, game, rng).await
+        .unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// The same as get_random_achievement_for_game but returns an error instead of panicking, for callers that can stop cleanly
+pub async fn try_get_random_achievement_for_game<R: Rng + ?Sized>(key : &str, steam_id : &str, game: &Game, rng: &mut R) -> Result<Option<GameAchievement>, String> {
     // Get the achievements for a specific game
-        let achievements = achievement_fetch::get_player_achievements(key, steam_id, &game.appid).await;
+        let achievements = achievement_fetch::try_get_player_achievements(key, steam_id, &game.appid).await?;
         if let Some(a) = achievements {
             // Get details of the achievements
-            let achievements: Vec<achievement_fetch::GameAchievement> = achievement_fetch::get_game_achievements(key, &game.appid).await;
+            let achievements: Vec<achievement_fetch::GameAchievement> = achievement_fetch::try_get_game_achievements(key, &game.appid).await?;
 
             // Load currently listed achievements
-            let current_goals_for_app: Vec<achievement_store::Achievement> = achievement_store::get_achievements_for_app(&game.appid).expect("Failed to load current goals");
+            let current_goals_for_app: Vec<achievement_store::Achievement> = achievement_store::get_achievements_for_app(&game.appid).map_err(|e| e.to_string())?;
 
             // Load excluded achievement
-            let excluded_achievement_for_app: Vec<excluded_achievement_store::ExcludedAchievement> = excluded_achievement_store::get_excluded_achievements_for_app(&game.appid).expect("Failed to load excluded achievements");
+            let excluded_achievement_for_app: Vec<excluded_achievement_store::ExcludedAchievement> = excluded_achievement_store::get_excluded_achievements_for_app(&game.appid).map_err(|e| e.to_string())?;
+
+            // Load achievements matched by an exclusion rule
+            exclusion_rules::fetch_rule_data(key, &game.appid).await?;
+            let names: Vec<String> = a.achievements.iter().map(|a| a.apiname.clone()).collect();
+            let rule_excluded_for_app: HashSet<String> = exclusion_rules::excluded_by_rules(&game.appid, &names)?;
+
+            // Load skipped achievements that are still on cooldown
+            let today = Local::now().date_naive();
+            let skipped_achievement_for_app: Vec<skipped_achievement_store::SkippedAchievement> = skipped_achievement_store::get_skipped_achievements_for_app(&game.appid).map_err(|e| e.to_string())?
+                .into_iter()
+                .filter(|s| s.on_cooldown(today))
+                .collect();
 
             // Randomly select achievement from game
-            let filter_to_unachieved: Vec<achievement_fetch::PlayerAchievement> = a.achievements
+            let mut filter_to_unachieved: Vec<achievement_fetch::PlayerAchievement> = a.achievements
                 .iter()
                 .filter(|a| a.achieved == 0) // Filter out achieved
                 .filter(|a| !current_goals_for_app.iter().any(|x| x.achievement_name == a.apiname)) // Filter out already in goals
                 .filter(|a| !excluded_achievement_for_app.iter().any(|x| x.achievement_name == a.apiname)) // Filter out any excluded achievements
+                .filter(|a| !rule_excluded_for_app.contains(&a.apiname)) // Filter out achievements matched by a rule
+                .filter(|a| !skipped_achievement_for_app.iter().any(|x| x.achievement_name == a.apiname)) // Filter out recently skipped achievements
                 .cloned()
                 .collect();
+            // Steam doesn't promise an order, sort so a seeded roll picks the same achievement every time
+            filter_to_unachieved.sort_by(|a, b| a.apiname.cmp(&b.apiname));
 
             // Check there is something still in it
             if filter_to_unachieved.is_empty() {
-                None
+                Ok(None)
             }
             else {
-                let mut rng = rand::rng();
-                let random_achievement = filter_to_unachieved.choose(&mut rng).unwrap();
-                Some(achievements
+                let random_achievement = filter_to_unachieved.choose(rng).unwrap();
+                Ok(Some(achievements
                     .iter()
-                    .find(|a| a.name == random_achievement.apiname).cloned().unwrap())
+                    .find(|a| a.name == random_achievement.apiname).cloned().unwrap()))
             }
         }
         else {
-            None
+            Ok(None)
         }
 }
 
-pub async fn refresh_game_completion_cache(key : &str, steam_id : &str, games: &Vec<game_fetch::Game>) {
+/// Remove a goal without excluding it, the achievement can't be rolled again until the cooldown has passed.
+/// Returns the removed goal and the day it becomes available again
+pub fn skip_goal(id: &i32) -> Result<(achievement_store::Achievement, NaiveDate), String> {
+    let goal = achievement_store::get_achievement(id).map_err(|e| e.to_string())?
+        .ok_or(format!("No goal with the id {id}"))?;
+    let today = Local::now().date_naive();
+    let cooldown = settings::get_skip_cooldown_days() as u64;
+    let available_after = today.checked_add_days(Days::new(cooldown)).ok_or("The skip cooldown is too long")?;
+    skipped_achievement_store::save_skipped_achievement(&goal.app_id, &goal.achievement_name, &goal.display_name, &today, &available_after).map_err(|e| e.to_string())?;
+    achievement_store::delete_achievement(id).map_err(|e| e.to_string())?;
+    Ok((goal, available_after))
+}
+
+/// Drop the cached completion for a game and recalculate it, used when its exclusions change
+pub async fn refresh_game_completion_for_app(key : &str, steam_id : &str, game: &game_fetch::Game) -> Result<(), String> {
+    game_completion_cache::delete_game_completion(&game.appid).map_err(|e| e.to_string())?;
+    refresh_game_completion_cache(key, steam_id, std::slice::from_ref(game)).await
+}
+
+/// Mark every goal set square whose achievement has been unlocked.
+/// Squares are matched against the cached unlocks, so skipped or removed goals are marked too
+fn complete_goal_set_items() -> Result<(), String> {
+    let unlocked: HashSet<(i32, String)> = achievement_unlock_store::get_unlocks().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|u| (u.app_id, u.achievement_name))
+        .collect();
+    for item in goal_set_store::get_unfinished_items().map_err(|e| e.to_string())? {
+        if unlocked.contains(&item) {
+            goal_set_store::complete_item(&item.0, &item.1).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// done and total count the schema checks of complete games as well as the games refreshed
+#[derive(Debug, Clone, Copy)]
+pub struct RefreshProgress {
+    pub done: usize,
+    pub total: usize, // Grows when a schema check finds a game that needs refreshing
+    pub resumed: usize, // Games left over from an earlier refresh that stopped
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshOutcome {
+    Completed,
+    Cancelled,
+}
+
+/// Refresh without progress or a way to cancel, Err when Steam can't be reached or the request limit is hit
+pub async fn refresh_game_completion_cache(key : &str, steam_id : &str, games: &[game_fetch::Game]) -> Result<(), String> {
+    refresh_game_completion_cache_with_progress(key, steam_id, games, |_| {}, &AtomicBool::new(false)).await?;
+    Ok(())
+}
+
+/// Refresh the completion of every game played since it was cached.
+/// The games to fetch are checkpointed, if the refresh stops part way the next run picks up the games left over.
+/// on_progress is called after each game, setting cancel stops cleanly before the next game
+pub async fn refresh_game_completion_cache_with_progress(key : &str, steam_id : &str, games: &[game_fetch::Game], mut on_progress: impl FnMut(RefreshProgress), cancel: &AtomicBool) -> Result<RefreshOutcome, String> {
     // Get cached completed games
     let completed_games_cache: HashMap<i32, game_completion_cache::GameCompletion> = game_completion_cache::get_game_completion()
-        .expect("Failed to load completed games")
+        .map_err(|e| e.to_string())?
         .iter()
         .map(|n| (n.app_id, n.clone()))
         .collect();
-    for game in games {
+    let checkpoint: HashSet<i32> = refresh_checkpoint_store::get_pending().map_err(|e| e.to_string())?
+        .into_iter()
+        .collect();
+    let unlocks_cached: HashSet<i32> = achievement_unlock_store::get_cached_app_ids().map_err(|e| e.to_string())?
+        .into_iter()
+        .collect();
+    // Games left from an earlier run go first
+    let mut to_refresh: Vec<&game_fetch::Game> = games.iter()
+        .filter(|g| checkpoint.contains(&g.appid))
+        .collect();
+    let resumed = to_refresh.len();
+    to_refresh.extend(games.iter()
+        .filter(|g| !checkpoint.contains(&g.appid))
         // Skip the game if no playtime
-        if game.playtime_forever == 0 {
-            continue;
-        }
-        // Check if cached and not played since
-        let cache_check = completed_games_cache.get(&game.appid);
-        if cache_check.is_some_and(|c| c.last_played == game.last_played) {
-            continue;
+        .filter(|g| g.playtime_forever > 0)
+        // Check if cached and not played since, games cached before unlocks were kept are fetched once more for the score
+        .filter(|g| completed_games_cache.get(&g.appid).is_none_or(|c| c.last_played != g.last_played || (c.achieved > 0 && !unlocks_cached.contains(&g.appid)))));
+    let app_ids: Vec<i32> = to_refresh.iter().map(|g| g.appid).collect();
+    refresh_checkpoint_store::save_pending(&app_ids).map_err(|e| e.to_string())?;
+
+    // Complete games are checked for new achievements first, one that gained some is refreshed below
+    let schema_checks = schema_change::apps_to_check(games);
+    let mut done = 0;
+    on_progress(RefreshProgress { done, total: schema_checks.len() + to_refresh.len(), resumed });
+    for app_id in &schema_checks {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(RefreshOutcome::Cancelled);
         }
-        // Get the achievements completed for that game
-        let player_achievements = achievement_fetch::get_player_achievements(key, steam_id, &game.appid).await;
-        if player_achievements.is_none() {
-            // Game has no achievements check if it is marked as completed or not
-            if let Some(target) = game_target_store::get_game_target(&game.appid).expect("Failed to load game target") {
-                if target.complete {
-                    game_completion_cache::save_game_completion(&game.appid, 100, game.last_played, false, true).expect("Failed to save game completion");
-                }
-                else {
-                    game_completion_cache::save_game_completion(&game.appid, 0, game.last_played, false, false).expect("Failed to save game completion");
-                }
-            }
-            else {
-                game_completion_cache::save_game_completion(&game.appid, 0, game.last_played, false, false).expect("Failed to save game completion");
+        if schema_change::check_schema(key, app_id).await?.is_some() && !to_refresh.iter().any(|g| g.appid == *app_id) {
+            if let Some(game) = games.iter().find(|g| g.appid == *app_id) {
+                refresh_checkpoint_store::save_pending(&[*app_id]).map_err(|e| e.to_string())?;
+                to_refresh.push(game);
             }
-            continue;
         }
-        let p = player_achievements.unwrap();
-        let unachieved: Vec<&achievement_fetch::PlayerAchievement> = p.achievements.iter()
-            .filter(|a| a.achieved==0)
-            .collect();
-        // Display if it is complete and save the current result
-        if unachieved.is_empty() {
-            game_completion_cache::save_game_completion(&game.appid, 100, game.last_played, true, true).expect("Failed to save game completion");
+        done += 1;
+        on_progress(RefreshProgress { done, total: schema_checks.len() + to_refresh.len(), resumed });
+    }
+
+    // Unlocks cached by an earlier refresh that stopped part way
+    complete_goal_set_items()?;
+
+    let total = schema_checks.len() + to_refresh.len();
+    for game in to_refresh {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(RefreshOutcome::Cancelled);
         }
-        else {
-            if let Some(target) = game_target_store::get_game_target(&game.appid).expect("Failed to load game target") {
-                if target.complete {
-                    game_completion_cache::save_game_completion(&game.appid, 100, game.last_played, true, false).expect("Failed to save game completion");
-                    continue;
-                }
-            }
-            // Check for any excluded achievements
-            let excluded_achievements: Vec<String> = excluded_achievement_store::get_excluded_achievements_for_app(&game.appid)
-                .expect("Failed to load excluded achievements")
+        // Get the achievements completed for that game
+        let player_achievements = achievement_fetch::try_get_player_achievements(key, steam_id, &game.appid).await?;
+        if let Some(p) = player_achievements {
+            // Check for any excluded achievements, including ones matched by a rule
+            exclusion_rules::fetch_rule_data(key, &game.appid).await?;
+            let mut excluded_achievements: Vec<String> = excluded_achievement_store::get_excluded_achievements_for_app(&game.appid)
+                .map_err(|e| e.to_string())?
                 .iter()
                 .map(|a| a.achievement_name.clone())
                 .collect();
-            let unachieved_with_excluded_count = unachieved.iter()
-                .filter(|a| !excluded_achievements.contains(&a.apiname))
-                .count();
-            if unachieved_with_excluded_count == 0 {
-                game_completion_cache::save_game_completion(&game.appid, 100, game.last_played, true, false)
-                    .expect("Failed to save game completion");
-            }
-            else {
-                let progress : i8 = (100.0 * (1.0 -( (unachieved_with_excluded_count as f32) / (p.achievements.len() as f32)))) as i8;
-                game_completion_cache::save_game_completion(&game.appid, progress, game.last_played, true, false)
-                    .expect("Failed to save game completion");
-            }
-            
+            let names: Vec<String> = p.achievements.iter().map(|a| a.apiname.clone()).collect();
+            excluded_achievements.extend(exclusion_rules::excluded_by_rules(&game.appid, &names)?);
+            let total = p.achievements.len() as i32;
+            let achieved = p.achievements.iter()
+                .filter(|a| a.achieved == 1)
+                .count() as i32;
+            let excluded = p.achievements.iter()
+                .filter(|a| a.achieved == 0 && excluded_achievements.contains(&a.apiname))
+                .count() as i32;
+            game_completion_cache::save_game_completion(&game.appid, total, achieved, excluded, game.last_played, true, achieved == total)
+                .map_err(|e| e.to_string())?;
+            let unlocks: Vec<achievement_unlock_store::AchievementUnlock> = p.achievements.iter()
+                .filter(|a| a.achieved == 1)
+                .map(|a| achievement_unlock_store::AchievementUnlock {
+                    app_id: game.appid,
+                    achievement_name: a.apiname.clone(),
+                    unlocked_at: a.unlocktime,
+                })
+                .collect();
+            achievement_unlock_store::save_unlocks_for_app(&game.appid, &unlocks).map_err(|e| e.to_string())?;
         }
+        else {
+            // Game has no achievements, completion is only set by marking the game target as complete
+            game_completion_cache::save_game_completion(&game.appid, 0, 0, 0, game.last_played, false, false).map_err(|e| e.to_string())?;
+        }
+        refresh_checkpoint_store::complete(&game.appid).map_err(|e| e.to_string())?;
+        done += 1;
+        on_progress(RefreshProgress { done, total, resumed });
     }
-}   
\ No newline at end of file
+    complete_goal_set_items()?;
+    snapshot::record_snapshot();
+    Ok(RefreshOutcome::Completed)
+}
@@ -1 +1,18 @@
-pub mod goals;
\ No newline at end of file
+pub mod goals;
+pub mod completion;
+pub mod backup;
+pub mod doctor;
+pub mod settings;
+pub mod snapshot;
+pub mod search;
+pub mod estimate;
+pub mod schema_change;
+pub mod roll;
+pub mod daily;
+pub mod bingo;
+pub mod goal_set;
+pub mod multi_roll;
+pub mod autopilot;
+pub mod exclusion_rules;
+pub mod score;
+pub mod time_estimate;
\ No newline at end of file
@@ -0,0 +1,98 @@
+use api::game_fetch::Game;
+use db::{achievement_store, game_completion_cache, goal_set_store, goal_set_store::{GoalSet, GoalSetItem, GoalSetKind}};
+use chrono::Local;
+
+use std::collections::HashSet;
+
+use crate::{goal_set, goal_set::RarityRule, goals, roll, roll::Roller};
+
+/// The limits shared by every goal in a multi roll
+#[derive(Debug, Clone, Default)]
+pub struct RollConstraints {
+    pub max_active_goals: Option<usize>, // Counts the goals already set
+    pub min_playtime: Option<i32>, // Minutes
+    pub max_playtime: Option<i32>, // Minutes
+    pub min_rarity: Option<f64>, // Percent of players with the achievement
+    pub max_rarity: Option<f64>, // Percent of players with the achievement
+}
+
+impl RollConstraints {
+    pub fn rarity_rule(&self) -> RarityRule {
+        if self.min_rarity.is_none() && self.max_rarity.is_none() {
+            return RarityRule::Any;
+        }
+        RarityRule::Require((self.min_rarity.unwrap_or(0.0), self.max_rarity.unwrap_or(100.0)))
+    }
+
+    fn allows_game(&self, game: &Game) -> bool {
+        self.min_playtime.is_none_or(|m| game.playtime_forever >= m)
+            && self.max_playtime.is_none_or(|m| game.playtime_forever <= m)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MultiRoll {
+    pub set: GoalSet,
+    pub items: Vec<GoalSetItem>,
+}
+
+/// Roll a goal from each of count different games and save them together as a goal set.
+/// Games that already have a goal are not picked, and a game is passed over when nothing in it meets the rarity limits
+pub async fn roll_goals(key : &str, steam_id : &str, games: &[Game], count: usize, constraints: &RollConstraints, roller: &mut Roller) -> Result<MultiRoll, String> {
+    if count == 0 {
+        return Err("Roll at least one goal".to_string());
+    }
+    goals::check_goal_limit(count)?;
+    let goals = achievement_store::get_achievements().map_err(|e| e.to_string())?;
+    if let Some(max) = constraints.max_active_goals {
+        if goals.len() + count > max {
+            return Err(format!("You have {active} goals, rolling {count} more would go over the limit of {max}", active = goals.len()));
+        }
+    }
+    if let (Some(min), Some(max)) = (constraints.min_playtime, constraints.max_playtime) {
+        if min > max {
+            return Err("The minimum playtime is more than the maximum".to_string());
+        }
+    }
+    if let RarityRule::Require((min, max)) = constraints.rarity_rule() {
+        if min > max {
+            return Err("The minimum rarity is more than the maximum".to_string());
+        }
+    }
+
+    // Only games with achievements left and no goal already can be picked
+    let with_goals: HashSet<i32> = goals.iter().map(|a| a.app_id).collect();
+    let unfinished: HashSet<i32> = game_completion_cache::get_game_completion().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|c| c.has_achievements && c.achieved < c.total)
+        .map(|c| c.app_id)
+        .collect();
+    let mut candidate_games: Vec<Game> = games.iter()
+        .filter(|g| unfinished.contains(&g.appid) && !with_goals.contains(&g.appid) && constraints.allows_game(g))
+        .cloned()
+        .collect();
+    if candidate_games.len() < count {
+        return Err(format!("Only {} games without a goal match, rolling {count} goals needs {count} games", candidate_games.len()));
+    }
+
+    let cooldown = roll::Cooldown::for_roller(roller);
+    let mut items: Vec<GoalSetItem> = Vec::new();
+    let mut picked_games: Vec<Game> = Vec::new();
+    while items.len() < count {
+        let Some(game) = roll::take_random_game(&mut candidate_games, &cooldown, roller.rng()) else {
+            return Err(format!("Ran out of games with achievements to pick after {} goals", items.len()));
+        };
+        if let Some(item) = goal_set::roll_item(key, steam_id, &game, items.len() as i32, constraints.rarity_rule(), roller).await? {
+            items.push(item);
+            picked_games.push(game);
+        }
+    }
+
+    let today = Local::now().date_naive();
+    let set_id = goal_set::save_with_goals(&format!("Session {today}"), &GoalSetKind::MultiRoll, count as i32, &items, &picked_games, roller)?;
+    let set = goal_set_store::get_goal_sets().map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|s| s.id == set_id)
+        .ok_or("Failed to load the new goal set".to_string())?;
+    Ok(MultiRoll { set, items })
+}
@@ -0,0 +1,226 @@
+use api::game_fetch::Game;
+use db::game_pick_store;
+use chrono::{DateTime, Local, NaiveDate};
+use rand::{distr::{Distribution, weighted::WeightedIndex}, rngs::ChaCha8Rng, Rng, RngExt, SeedableRng};
+
+use std::collections::HashMap;
+
+use crate::settings;
+
+/// The random number generator behind every roll.
+/// ChaCha8 gives the same numbers for a seed on every platform, so a shared seed rolls the same goals for everyone
+pub struct Roller {
+    seed: u64,
+    seeded: bool, // The seed was given rather than picked
+    rng: ChaCha8Rng,
+}
+
+impl Roller {
+    /// Use the seed given or pick a new one, the seed is kept so the roll can be repeated
+    pub fn new(seed: Option<u64>) -> Self {
+        let seeded = seed.is_some();
+        let seed = seed.unwrap_or_else(rand::random);
+        Roller {
+            seed,
+            seeded,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn seeded(&self) -> bool {
+        self.seeded
+    }
+
+    /// The seed as stored against a goal, SQLite only has signed integers
+    pub fn stored_seed(&self) -> i64 {
+        self.seed as i64
+    }
+
+    pub fn rng(&mut self) -> &mut impl Rng {
+        &mut self.rng
+    }
+}
+
+/// Makes games picked or played in the last few days less likely to be rolled again.
+/// The chance climbs back each day, a game picked today is cooldown + 1 times less likely than one outside the cooldown
+pub struct Cooldown {
+    days: i64,
+    today: NaiveDate,
+    picks: HashMap<i32, NaiveDate>,
+}
+
+impl Cooldown {
+    /// Uses the cooldown from the settings and the saved picks
+    pub fn load() -> Self {
+        Cooldown {
+            days: settings::get_pick_cooldown_days(),
+            today: Local::now().date_naive(),
+            picks: game_pick_store::get_game_picks().expect("Failed to load game picks")
+                .into_iter()
+                .map(|p| (p.app_id, p.picked_on))
+                .collect(),
+        }
+    }
+
+    /// Every game has the same weight
+    pub fn none() -> Self {
+        Cooldown {
+            days: 0,
+            today: Local::now().date_naive(),
+            picks: HashMap::new(),
+        }
+    }
+
+    /// A roll with a given seed ignores the cooldown, it depends on this machine's picks so the seed would roll a different game for someone else
+    pub fn for_roller(roller: &Roller) -> Self {
+        if roller.seeded() {
+            Cooldown::none()
+        }
+        else {
+            Cooldown::load()
+        }
+    }
+
+    /// True when every game has the same weight, so the seed alone decides the roll
+    pub fn is_none(&self) -> bool {
+        self.days == 0
+    }
+
+    /// Remember the game was picked today
+    pub fn record(&mut self, game: &Game) {
+        game_pick_store::save_game_pick(&game.appid, &self.today).expect("Failed to save the game pick");
+        self.picks.insert(game.appid, self.today);
+    }
+
+    pub fn weight(&self, game: &Game) -> f64 {
+        if self.days == 0 {
+            return 1.0;
+        }
+        let last_played = DateTime::from_timestamp(game.last_played, 0)
+            .filter(|_| game.last_played > 0)
+            .map(|d| d.with_timezone(&Local).date_naive());
+        let days_since = |day: Option<NaiveDate>| day
+            .map(|d| (self.today - d).num_days().max(0))
+            .unwrap_or(self.days);
+        let factor = |day: Option<NaiveDate>| ((days_since(day) + 1) as f64 / (self.days + 1) as f64).min(1.0);
+        factor(self.picks.get(&game.appid).copied()) * factor(last_played)
+    }
+}
+
+/// Remove and return a random game weighted by the cooldown, the games are sorted first so the pick only depends on the seed,
+/// the games owned and the cooldown
+pub fn take_random_game<R: Rng + ?Sized>(games: &mut Vec<Game>, cooldown: &Cooldown, rng: &mut R) -> Option<Game> {
+    if games.is_empty() {
+        return None;
+    }
+    games.sort_by_key(|g| g.appid);
+    let index = WeightedIndex::new(games.iter().map(|g| cooldown.weight(g)))
+        .map(|w| w.sample(rng))
+        .unwrap_or_else(|_| rng.random_range(..games.len()));
+    Some(games.remove(index))
+}
+
+/// Remove and return a random item, sorted first for the same reason as take_random_game
+pub fn take_random<T: Ord, R: Rng + ?Sized>(items: &mut Vec<T>, rng: &mut R) -> Option<T> {
+    if items.is_empty() {
+        return None;
+    }
+    items.sort();
+    let index = rng.random_range(..items.len());
+    Some(items.remove(index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Days;
+
+    fn game(appid: i32, last_played: i64) -> Game {
+        Game { appid, name: format!("Game {appid}"), playtime_forever: 60, last_played }
+    }
+
+    fn cooldown(days: i64, picks: &[(i32, u64)]) -> Cooldown {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).unwrap();
+        Cooldown {
+            days,
+            today,
+            picks: picks.iter().map(|(app_id, days_ago)| (*app_id, today - Days::new(*days_ago))).collect(),
+        }
+    }
+
+    #[test]
+    fn roller_repeats_for_a_seed() {
+        let mut first = Roller::new(Some(42));
+        let mut second = Roller::new(Some(42));
+        let first_rolls: Vec<u64> = (0..10).map(|_| first.rng().random()).collect();
+        let second_rolls: Vec<u64> = (0..10).map(|_| second.rng().random()).collect();
+        assert_eq!(first_rolls, second_rolls);
+        assert_eq!(first.seed(), 42);
+    }
+
+    #[test]
+    fn take_random_ignores_the_order() {
+        let items: Vec<i32> = (1..=20).collect();
+        let mut reversed = items.clone();
+        reversed.reverse();
+        let take_all = |mut items: Vec<i32>| {
+            let mut roller = Roller::new(Some(7));
+            let mut taken = Vec::new();
+            while let Some(i) = take_random(&mut items, roller.rng()) {
+                taken.push(i);
+            }
+            taken
+        };
+        assert_eq!(take_all(items), take_all(reversed));
+    }
+
+    #[test]
+    fn take_random_game_ignores_the_order() {
+        let cooldown = cooldown(7, &[(3, 0), (5, 2), (8, 6)]);
+        let games: Vec<Game> = (1..=10).map(|i| game(i, 0)).collect();
+        let mut reversed = games.clone();
+        reversed.reverse();
+        let take_all = |mut games: Vec<Game>| {
+            let mut roller = Roller::new(Some(7));
+            let mut taken = Vec::new();
+            while let Some(g) = take_random_game(&mut games, &cooldown, roller.rng()) {
+                taken.push(g.appid);
+            }
+            taken
+        };
+        assert_eq!(take_all(games), take_all(reversed));
+    }
+
+    #[test]
+    fn cooldown_weight_bounds() {
+        let cooldown = cooldown(7, &[(1, 0), (2, 3), (3, 7), (4, 30)]);
+        // Picked today is cooldown + 1 times less likely
+        assert_eq!(cooldown.weight(&game(1, 0)), 1.0 / 8.0);
+        assert_eq!(cooldown.weight(&game(2, 0)), 4.0 / 8.0);
+        // Back to full weight once the cooldown has passed
+        assert_eq!(cooldown.weight(&game(3, 0)), 1.0);
+        assert_eq!(cooldown.weight(&game(4, 0)), 1.0);
+        assert_eq!(cooldown.weight(&game(5, 0)), 1.0);
+        // Picked and played today multiplies both
+        let played_today = cooldown.today.and_hms_opt(12, 0, 0).unwrap().and_local_timezone(Local).unwrap().timestamp();
+        assert_eq!(cooldown.weight(&game(1, played_today)), 1.0 / 64.0);
+        for app_id in 1..=5 {
+            let weight = cooldown.weight(&game(app_id, played_today));
+            assert!(weight > 0.0 && weight <= 1.0);
+        }
+    }
+
+    #[test]
+    fn no_cooldown_weighs_every_game_the_same() {
+        let cooldown = cooldown(0, &[(1, 0)]);
+        assert_eq!(cooldown.weight(&game(1, 0)), 1.0);
+        let mut none = Cooldown::none();
+        none.picks.insert(1, none.today);
+        assert_eq!(none.weight(&game(1, 0)), 1.0);
+    }
+}
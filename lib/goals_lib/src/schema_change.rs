@@ -0,0 +1,48 @@
+use api::{achievement_fetch, game_fetch::Game};
+use db::{game_completion_cache, schema_change_store, schema_change_store::SchemaChange};
+use chrono::Local;
+
+use std::collections::HashSet;
+
+use crate::{completion, settings};
+
+/// Complete games with achievements whose schema has not been checked today, only these can silently drop below 100
+pub fn apps_to_check(games: &[Game]) -> Vec<i32> {
+    let owned: HashSet<i32> = games.iter().map(|g| g.appid).collect();
+    let checked: HashSet<i32> = schema_change_store::get_checked_on(&Local::now().date_naive()).expect("Failed to load schema checks")
+        .into_iter()
+        .collect();
+    let mut app_ids: Vec<i32> = completion::get_game_progress(settings::get_completion_policy())
+        .into_values()
+        .filter(|p| p.completion.has_achievements && (p.complete || p.perfect))
+        .map(|p| p.completion.app_id)
+        .filter(|app_id| owned.contains(app_id) && !checked.contains(app_id))
+        .collect();
+    app_ids.sort();
+    app_ids
+}
+
+/// Fetch the schema of a complete game from apps_to_check and compare its achievement count with the cached completion.
+/// The cached total is what the game was counted complete against, the stored schema can't be used as any fetch replaces it.
+/// A game that gained achievements is recorded as a change and its cached completion dropped so the refresh recalculates it
+pub async fn check_schema(key : &str, app_id: &i32) -> Result<Option<SchemaChange>, String> {
+    let today = Local::now().date_naive();
+    let cached_total = game_completion_cache::get_game_completion().map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|c| c.app_id == *app_id)
+        .map(|c| c.total);
+    // Nothing to compare against, the refresh works out its completion from scratch
+    let Some(previous_total) = cached_total else {
+        return Ok(None);
+    };
+    let new_total = achievement_fetch::try_get_game_achievements(key, app_id).await?.len() as i32;
+    let mut detected = None;
+    if new_total > previous_total {
+        schema_change_store::save_schema_change(app_id, &today, &previous_total, &new_total).map_err(|e| e.to_string())?;
+        game_completion_cache::delete_game_completion(app_id).map_err(|e| e.to_string())?;
+        detected = Some(SchemaChange { id: 0, app_id: *app_id, detected_on: today, previous_total, new_total });
+    }
+    // Checked games are skipped for the rest of the day, so a refresh that stops part way doesn't check them again
+    schema_change_store::save_checked(app_id, &today).map_err(|e| e.to_string())?;
+    Ok(detected)
+}
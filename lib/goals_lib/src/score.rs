@@ -0,0 +1,108 @@
+use api::global_achievement_fetch;
+use db::{achievement_rarity_store, achievement_unlock_store};
+use chrono::{Datelike, Local, TimeZone};
+
+use std::collections::{HashMap, HashSet};
+
+/// Points for an unlocked achievement, 10 for one every player has, going up as fewer players have it.
+/// Rarity below 1% scores the same as 1%, so one achievement is worth at most 1000 points
+pub fn points(percent: f64) -> i32 {
+    (10.0 * 100.0 / percent.clamp(1.0, 100.0)).round() as i32
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GameScore {
+    pub app_id: i32,
+    pub points: i32,
+    pub points_this_month: i32,
+    pub unlocked: i32,
+    pub unrated: i32, // Unlocks with no rarity cached, these score nothing until it is fetched
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scores {
+    pub games: HashMap<i32, GameScore>,
+    pub total: i32,
+    pub this_month: i32,
+    pub unrated: i32,
+}
+
+/// Score every game from the cached unlocks and rarity, the unlocks are kept by the completion refresh
+pub fn get_scores() -> Result<Scores, String> {
+    let rarity: HashMap<(i32, String), f64> = achievement_rarity_store::get_all_rarity().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| ((r.app_id, r.achievement_name), r.percent))
+        .collect();
+    let month_start = month_start();
+
+    let mut scores = Scores::default();
+    for unlock in achievement_unlock_store::get_unlocks().map_err(|e| e.to_string())? {
+        let game = scores.games.entry(unlock.app_id).or_insert(GameScore { app_id: unlock.app_id, ..GameScore::default() });
+        game.unlocked += 1;
+        let Some(percent) = rarity.get(&(unlock.app_id, unlock.achievement_name)) else {
+            game.unrated += 1;
+            scores.unrated += 1;
+            continue;
+        };
+        let points = points(*percent);
+        game.points += points;
+        scores.total += points;
+        if unlock.unlocked_at >= month_start {
+            game.points_this_month += points;
+            scores.this_month += points;
+        }
+    }
+    Ok(scores)
+}
+
+/// Unix time of midnight on the first day of this month
+fn month_start() -> i64 {
+    let today = Local::now().date_naive();
+    today.with_day(1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .and_then(|d| Local.from_local_datetime(&d).earliest())
+        .map(|d| d.timestamp())
+        .unwrap_or_default()
+}
+
+/// The apps with unlocks but no rarity cached
+pub fn apps_missing_rarity() -> Result<Vec<i32>, String> {
+    let rated: HashSet<i32> = achievement_rarity_store::get_cached_app_ids().map_err(|e| e.to_string())?
+        .into_iter()
+        .collect();
+    let unlocked: HashSet<i32> = achievement_unlock_store::get_unlocks().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|u| u.app_id)
+        .collect();
+    let mut apps: Vec<i32> = unlocked.difference(&rated).copied().collect();
+    apps.sort();
+    Ok(apps)
+}
+
+/// Fetch the rarity of every app with unlocks that has none cached, returns how many were fetched
+pub async fn fetch_missing_rarity() -> Result<usize, String> {
+    let apps = apps_missing_rarity()?;
+    for app_id in &apps {
+        global_achievement_fetch::try_get_global_achievement_percentages(app_id).await?;
+    }
+    Ok(apps.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_by_rarity() {
+        assert_eq!(points(100.0), 10);
+        assert_eq!(points(50.0), 20);
+        assert_eq!(points(3.0), 333);
+        assert_eq!(points(1.0), 1000);
+    }
+
+    #[test]
+    fn rarity_below_one_percent_is_capped() {
+        assert_eq!(points(0.5), 1000);
+        assert_eq!(points(0.0), 1000);
+    }
+}
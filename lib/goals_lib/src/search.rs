@@ -0,0 +1,62 @@
+use api::{achievement_fetch, game_fetch::Game};
+use db::{achievement_schema_store, achievement_store};
+
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub app_id: i32,
+    pub achievement_name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub is_goal: bool,
+}
+
+/// Fetch and cache the schema of every played game that is not cached yet, returns how many were fetched.
+/// This costs a request per game so only run it when asked to
+pub async fn index_schemas(key : &str, games: &[Game]) -> usize {
+    let cached: HashSet<i32> = achievement_schema_store::get_cached_app_ids().expect("Failed to load cached schemas")
+        .into_iter()
+        .collect();
+    let mut fetched = 0;
+    for game in games.iter().filter(|g| g.playtime_forever > 0 && !cached.contains(&g.appid)) {
+        // Fetching the schema also saves it to the search index
+        achievement_fetch::get_game_achievements(key, &game.appid).await;
+        fetched += 1;
+    }
+    fetched
+}
+
+/// Search achievement names and descriptions in the cached schemas and goals, goals are indexed when they are saved.
+/// Only achievements that are not yet achieved are returned when unachieved_only is set, this needs a request per matching game
+/// so check estimate::search first
+pub async fn search(key : &str, steam_id : &str, query: &str, unachieved_only: bool) -> Result<Vec<SearchResult>, String> {
+    let goal_set: HashSet<(i32, String)> = achievement_store::get_achievements().map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|g| (g.app_id, g.achievement_name))
+        .collect();
+
+    let matches = achievement_schema_store::search(query).map_err(|e| e.to_string())?;
+
+    let mut achieved: HashMap<i32, HashSet<String>> = HashMap::new();
+    if unachieved_only {
+        let app_ids: HashSet<i32> = matches.iter().map(|m| m.app_id).collect();
+        for app_id in app_ids {
+            let player = achievement_fetch::try_get_player_achievements(key, steam_id, &app_id).await?;
+            achieved.insert(app_id, player
+                .map(|p| p.achievements.into_iter().filter(|a| a.achieved == 1).map(|a| a.apiname).collect())
+                .unwrap_or_default());
+        }
+    }
+
+    Ok(matches.into_iter()
+        .filter(|m| !unachieved_only || !achieved.get(&m.app_id).is_some_and(|a| a.contains(&m.achievement_name)))
+        .map(|m| SearchResult {
+            is_goal: goal_set.contains(&(m.app_id, m.achievement_name.clone())),
+            app_id: m.app_id,
+            achievement_name: m.achievement_name,
+            display_name: m.display_name,
+            description: m.description,
+        })
+        .collect())
+}
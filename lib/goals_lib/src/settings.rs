@@ -0,0 +1,124 @@
+use db::settings_store;
+
+use crate::{autopilot::Autopilot, completion::CompletionPolicy};
+
+const SKIP_COOLDOWN_DAYS: &str = "skip_cooldown_days";
+pub const DEFAULT_SKIP_COOLDOWN_DAYS: i64 = 30;
+
+/// How many days a skipped achievement is left out of random rolls
+pub fn get_skip_cooldown_days() -> i64 {
+    settings_store::get_setting(SKIP_COOLDOWN_DAYS).expect("Failed to load settings")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_SKIP_COOLDOWN_DAYS)
+}
+
+pub fn set_skip_cooldown_days(days: i64) -> Result<(), String> {
+    if days < 0 {
+        return Err("The skip cooldown can't be negative".to_string());
+    }
+    settings_store::save_setting(SKIP_COOLDOWN_DAYS, &days.to_string()).map_err(|e| e.to_string())
+}
+
+const DAILY_CHALLENGE_SEED: &str = "daily_challenge_seed";
+
+/// Mixed with the date to pick the daily challenge, everyone with the same seed and games gets the same challenge
+pub fn get_daily_challenge_seed() -> u64 {
+    settings_store::get_setting(DAILY_CHALLENGE_SEED).expect("Failed to load settings")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default()
+}
+
+pub fn set_daily_challenge_seed(seed: u64) -> Result<(), String> {
+    settings_store::save_setting(DAILY_CHALLENGE_SEED, &seed.to_string()).map_err(|e| e.to_string())
+}
+
+const PICK_COOLDOWN_DAYS: &str = "pick_cooldown_days";
+pub const DEFAULT_PICK_COOLDOWN_DAYS: i64 = 7;
+
+/// How many days a game picked or played recently is less likely to be rolled
+pub fn get_pick_cooldown_days() -> i64 {
+    settings_store::get_setting(PICK_COOLDOWN_DAYS).expect("Failed to load settings")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PICK_COOLDOWN_DAYS)
+}
+
+pub fn set_pick_cooldown_days(days: i64) -> Result<(), String> {
+    if days < 0 {
+        return Err("The pick cooldown can't be negative".to_string());
+    }
+    settings_store::save_setting(PICK_COOLDOWN_DAYS, &days.to_string()).map_err(|e| e.to_string())
+}
+
+const GOAL_LIMIT: &str = "goal_limit";
+const GAME_GOAL_LIMIT: &str = "game_goal_limit";
+
+/// The most goals that can be active at once, None when there is no limit
+pub fn get_goal_limit() -> Option<i32> {
+    settings_store::get_setting(GOAL_LIMIT).expect("Failed to load settings")
+        .and_then(|v| v.parse().ok())
+}
+
+/// The most goals that can be active at once for a single game, None when there is no limit
+pub fn get_game_goal_limit() -> Option<i32> {
+    settings_store::get_setting(GAME_GOAL_LIMIT).expect("Failed to load settings")
+        .and_then(|v| v.parse().ok())
+}
+
+/// A limit of 0 removes it
+pub fn set_goal_limit(limit: i32) -> Result<(), String> {
+    save_limit(GOAL_LIMIT, limit)
+}
+
+/// A limit of 0 removes it
+pub fn set_game_goal_limit(limit: i32) -> Result<(), String> {
+    save_limit(GAME_GOAL_LIMIT, limit)
+}
+
+fn save_limit(key: &str, limit: i32) -> Result<(), String> {
+    if limit < 0 {
+        return Err("A goal limit can't be negative".to_string());
+    }
+    if limit == 0 {
+        settings_store::delete_setting(key).map_err(|e| e.to_string())
+    }
+    else {
+        settings_store::save_setting(key, &limit.to_string()).map_err(|e| e.to_string())
+    }
+}
+
+const AUTOPILOT: &str = "autopilot";
+
+/// How completed goals are replaced when the goals sync
+pub fn get_autopilot() -> Autopilot {
+    settings_store::get_setting(AUTOPILOT).expect("Failed to load settings")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_default()
+}
+
+pub fn set_autopilot(autopilot: Autopilot) -> Result<(), String> {
+    settings_store::save_setting(AUTOPILOT, autopilot.as_str()).map_err(|e| e.to_string())
+}
+
+const COMPLETION_FORMULA: &str = "completion_formula";
+const COMPLETION_THRESHOLD: &str = "completion_threshold";
+const TARGETS_PERFECT: &str = "targets_perfect";
+
+/// What counts as complete and perfect everywhere completion is shown
+pub fn get_completion_policy() -> CompletionPolicy {
+    let setting = |key| settings_store::get_setting(key).expect("Failed to load settings");
+    let default = CompletionPolicy::default();
+    CompletionPolicy {
+        formula: setting(COMPLETION_FORMULA).and_then(|v| v.parse().ok()).unwrap_or(default.formula),
+        complete_threshold: setting(COMPLETION_THRESHOLD).and_then(|v| v.parse().ok()).unwrap_or(default.complete_threshold),
+        targets_perfect: setting(TARGETS_PERFECT).and_then(|v| v.parse().ok()).unwrap_or(default.targets_perfect),
+    }
+}
+
+pub fn set_completion_policy(policy: &CompletionPolicy) -> Result<(), String> {
+    if !(1..=100).contains(&policy.complete_threshold) {
+        return Err("The completion threshold must be between 1 and 100".to_string());
+    }
+    settings_store::save_setting(COMPLETION_FORMULA, &policy.formula.to_string()).map_err(|e| e.to_string())?;
+    settings_store::save_setting(COMPLETION_THRESHOLD, &policy.complete_threshold.to_string()).map_err(|e| e.to_string())?;
+    settings_store::save_setting(TARGETS_PERFECT, &policy.targets_perfect.to_string()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,29 @@
+use db::{progress_snapshot_store, progress_snapshot_store::ProgressSnapshot, request_store};
+use chrono::Local;
+
+use crate::{completion, settings};
+
+/// Save today's totals from the completion cache, run after each sync so the cache is up to date
+pub fn record_snapshot() -> ProgressSnapshot {
+    let progress = completion::get_game_progress(settings::get_completion_policy());
+    let played_with_achievements: Vec<i8> = progress.values()
+        .filter(|p| p.completion.has_achievements)
+        .map(|p| p.progress)
+        .collect();
+    let average_completion = if played_with_achievements.is_empty() {
+        0.0
+    }
+    else {
+        played_with_achievements.iter().map(|p| *p as f64).sum::<f64>() / played_with_achievements.len() as f64
+    };
+    let snapshot = ProgressSnapshot {
+        date: Local::now().date_naive(),
+        achievements_unlocked: progress.values().map(|p| p.completion.achieved).sum(),
+        games_completed: progress.values().filter(|p| p.complete).count() as i32,
+        games_perfected: progress.values().filter(|p| p.perfect).count() as i32,
+        average_completion,
+        request_count: request_store::get_count().expect("Failed to load request count"),
+    };
+    progress_snapshot_store::save_progress_snapshot(&snapshot).expect("Failed to save progress snapshot");
+    snapshot
+}
@@ -0,0 +1,159 @@
+use api::game_fetch::Game;
+use db::{hours_to_beat_store, hours_to_beat_store::HoursToBeat};
+
+use std::{collections::HashMap, fs};
+
+use crate::{completion, completion::{CompletionPolicy, GameProgress}};
+
+/// What the hours left in a game were worked out from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EstimateSource {
+    HoursToBeat, // The imported hours to beat, scaled by the progress
+    Playtime, // The playtime so far, extended at the same rate to the achievements left
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TimeEstimate {
+    pub app_id: i32,
+    pub hours_left: f64,
+    pub source: EstimateSource,
+}
+
+impl TimeEstimate {
+    pub fn display(&self) -> String {
+        match self.source {
+            EstimateSource::HoursToBeat => format!("{:.1}h", self.hours_left),
+            // Only a guess from the pace so far
+            EstimateSource::Playtime => format!("~{:.1}h", self.hours_left),
+        }
+    }
+}
+
+/// Estimate the hours left to complete a game.
+/// With an hours to beat the part of it matching the progress is left, or the hours not played yet when there is no progress.
+/// Without one the playtime so far is extended to the achievements left, which needs some progress to go on
+pub fn estimate(game: &Game, progress: Option<&GameProgress>, hours_to_beat: Option<f64>) -> Option<TimeEstimate> {
+    let played = game.playtime_forever as f64 / 60.0;
+    let percent = progress.map(|p| p.progress as f64).unwrap_or(0.0);
+    let source = if hours_to_beat.is_some() { EstimateSource::HoursToBeat } else { EstimateSource::Playtime };
+    let hours_left = if progress.is_some_and(|p| p.complete) {
+        0.0
+    }
+    else if let Some(hours) = hours_to_beat {
+        if percent > 0.0 { hours * (100.0 - percent) / 100.0 } else { (hours - played).max(0.0) }
+    }
+    else if percent > 0.0 && played > 0.0 {
+        played * (100.0 - percent) / percent
+    }
+    else {
+        return None;
+    };
+    Some(TimeEstimate { app_id: game.appid, hours_left, source })
+}
+
+/// Estimate every game that has enough to go on
+pub fn get_estimates(games: &[Game], policy: CompletionPolicy) -> HashMap<i32, TimeEstimate> {
+    let game_progress: HashMap<i32, GameProgress> = completion::get_game_progress(policy);
+    let hours_to_beat: HashMap<i32, f64> = hours_to_beat_store::get_hours_to_beat().expect("Failed to load hours to beat")
+        .into_iter()
+        .map(|h| (h.app_id, h.hours))
+        .collect();
+    games.iter()
+        .filter_map(|g| estimate(g, game_progress.get(&g.appid), hours_to_beat.get(&g.appid).copied()))
+        .map(|e| (e.app_id, e))
+        .collect()
+}
+
+/// The games not yet complete that are estimated to take at most the hours given, games with no estimate are left out
+pub fn games_under(games: Vec<Game>, hours: f64, policy: CompletionPolicy) -> Vec<Game> {
+    let estimates = get_estimates(&games, policy);
+    games.into_iter()
+        .filter(|g| estimates.get(&g.appid).is_some_and(|e| e.hours_left > 0.0 && e.hours_left <= hours))
+        .collect()
+}
+
+/// Import hours to beat from a csv file with a line of app_id,hours per game, a header line is skipped.
+/// Every line is checked before any are saved, returns how many were imported
+pub fn import_hours_to_beat(path: &str) -> Result<usize, String> {
+    let csv = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut imported: Vec<HoursToBeat> = Vec::new();
+    for (i, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((app_id, hours)) = line.split_once(',') else {
+            return Err(format!("Line {} should be app_id,hours", i + 1));
+        };
+        let Ok(app_id) = app_id.trim().parse::<i32>() else {
+            if i == 0 {
+                continue;
+            }
+            return Err(format!("Invalid app id {app_id} on line {}", i + 1));
+        };
+        let hours: f64 = hours.trim().parse().map_err(|_| format!("Invalid hours {hours} on line {}", i + 1))?;
+        if !hours.is_finite() || hours <= 0.0 {
+            return Err(format!("The hours on line {} must be more than 0", i + 1));
+        }
+        imported.push(HoursToBeat { app_id, hours });
+    }
+    for h in &imported {
+        hours_to_beat_store::save_hours_to_beat(&h.app_id, &h.hours).map_err(|e| e.to_string())?;
+    }
+    Ok(imported.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use db::game_completion_cache::GameCompletion;
+
+    fn game(minutes_played: i32) -> Game {
+        Game { appid: 1, name: "Game".to_string(), playtime_forever: minutes_played, last_played: 0 }
+    }
+
+    fn progress(progress: i8, complete: bool) -> GameProgress {
+        GameProgress {
+            completion: GameCompletion { app_id: 1, total: 100, achieved: progress as i32, excluded: 0, last_played: 0, has_achievements: true, perfect: complete },
+            progress,
+            complete,
+            perfect: complete,
+        }
+    }
+
+    #[test]
+    fn hours_to_beat_scales_with_progress() {
+        let estimate = estimate(&game(600), Some(&progress(25, false)), Some(20.0)).unwrap();
+        assert_eq!(estimate.hours_left, 15.0);
+        assert_eq!(estimate.source, EstimateSource::HoursToBeat);
+    }
+
+    #[test]
+    fn hours_to_beat_without_progress_takes_off_playtime() {
+        assert_eq!(estimate(&game(300), None, Some(20.0)).unwrap().hours_left, 15.0);
+        assert_eq!(estimate(&game(300), Some(&progress(0, false)), Some(20.0)).unwrap().hours_left, 15.0);
+        // Played for longer than the hours to beat
+        assert_eq!(estimate(&game(1800), None, Some(20.0)).unwrap().hours_left, 0.0);
+    }
+
+    #[test]
+    fn playtime_extends_to_the_achievements_left() {
+        let estimate = estimate(&game(600), Some(&progress(25, false)), None).unwrap();
+        assert_eq!(estimate.hours_left, 30.0);
+        assert_eq!(estimate.source, EstimateSource::Playtime);
+    }
+
+    #[test]
+    fn playtime_needs_progress() {
+        assert!(estimate(&game(600), None, None).is_none());
+        assert!(estimate(&game(600), Some(&progress(0, false)), None).is_none());
+        assert!(estimate(&game(0), Some(&progress(25, false)), None).is_none());
+    }
+
+    #[test]
+    fn complete_games_have_nothing_left() {
+        assert_eq!(estimate(&game(600), Some(&progress(100, true)), Some(20.0)).unwrap().hours_left, 0.0);
+        assert_eq!(estimate(&game(600), Some(&progress(100, true)), None).unwrap().hours_left, 0.0);
+    }
+}